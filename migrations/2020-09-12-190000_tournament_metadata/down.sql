@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+alter table tournaments
+    drop column slot,
+    drop column title,
+    drop column status,
+    drop column created_at,
+    drop column finished_at;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+alter table tournaments
+    add column slot integer not null default 1,
+    add column title varchar(255),
+    add column status varchar(16) not null default 'open',
+    add column created_at timestamp not null default current_timestamp,
+    add column finished_at timestamp null;
+
+-- every pick knocks one anime out, so a tournament is finished once it's down to one
+update tournaments set status = 'finished'
+where (select count(*) from tournament_anime where tournament_anime.tournament = tournaments.id)
+    - (select count(*) from decisions where decisions.tournament = tournaments.id and decisions.pick is not null) <= 1;
@@ -0,0 +1,13 @@
+-- This file should undo anything in `up.sql`
+alter table anime
+    add column slot1 boolean not null default false,
+    add column slot2 boolean not null default false,
+    add column slot3 boolean not null default false;
+
+update anime set slot1 = true where id in (select anime from anime_slots where slot = 1);
+update anime set slot2 = true where id in (select anime from anime_slots where slot = 2);
+update anime set slot3 = true where id in (select anime from anime_slots where slot = 3);
+
+drop table anime_slots;
+
+drop table slots;
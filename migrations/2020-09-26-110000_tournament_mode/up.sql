@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+alter table tournaments
+    add column mode varchar(16) not null default 'elimination';
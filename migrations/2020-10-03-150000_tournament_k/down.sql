@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+alter table tournaments
+    drop column k;
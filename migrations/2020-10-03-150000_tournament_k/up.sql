@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+-- how many anime a top-k tournament picks
+alter table tournaments
+    add column k integer;
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+delete from decisions where voter <> 1;
+
+alter table decisions
+    drop primary key,
+    drop column voter,
+    add primary key (tournament, left_anime, right_anime);
+
+drop table tournament_members;
+
+drop table members;
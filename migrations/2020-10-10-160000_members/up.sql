@@ -0,0 +1,23 @@
+-- Your SQL goes here
+
+create table members (
+    id integer unique not null auto_increment primary key,
+    name varchar(255) not null unique
+);
+
+-- who votes in each tournament
+create table tournament_members (
+    tournament integer not null,
+    member integer not null,
+    primary key (tournament, member)
+);
+
+-- the decisions so far were all made by one person
+insert into members (id, name) values (1, 'me');
+insert into tournament_members (tournament, member) select id, 1 from tournaments;
+
+-- every voter answers the same pairs, so the voter is part of the key
+alter table decisions
+    add column voter integer not null default 1,
+    drop primary key,
+    add primary key (tournament, left_anime, right_anime, voter);
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+-- every rating an anime has had, one row each time a decision changed it;
+-- the latest row for an anime and system is its current rating
+create table ratings (
+    id integer unique not null auto_increment primary key,
+    anime integer not null,
+    system varchar(16) not null,
+    rating double not null,
+    deviation double,
+    volatility double,
+    tournament integer not null,
+    created_at timestamp not null default current_timestamp
+);
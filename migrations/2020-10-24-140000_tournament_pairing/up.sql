@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+-- how an elimination tournament picks its next pair
+alter table tournaments
+    add column pairing varchar(16) not null default 'lowest-pick';
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+drop table bracket_matches;
+
+alter table tournaments
+    modify mode varchar(16) not null default 'elimination';
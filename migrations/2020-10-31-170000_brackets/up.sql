@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+-- the matches of a bracket tournament, numbered in the order they're played;
+-- each side is a seeded anime, or the winner or loser of an earlier match
+create table bracket_matches (
+    tournament integer not null,
+    number integer not null,
+    bracket varchar(16) not null,
+    round integer not null,
+    left_anime integer,
+    left_winner_of integer,
+    left_loser_of integer,
+    right_anime integer,
+    right_winner_of integer,
+    right_loser_of integer,
+    primary key (tournament, number)
+);
+
+-- room for "double-elimination"
+alter table tournaments
+    modify mode varchar(32) not null default 'elimination';
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+-- how many rounds a swiss tournament plays, or enough to find a clear winner
+alter table tournaments
+    add column rounds integer;
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+
+-- each member's approval ballot: whether they ticked each anime
+create table approval_ballots (
+    tournament integer not null,
+    voter integer not null,
+    anime integer not null,
+    approved boolean not null,
+    primary key (tournament, voter, anime)
+);
+
+-- each member's ranked ballot: where they put each anime, 1 for first
+create table ranked_ballots (
+    tournament integer not null,
+    voter integer not null,
+    anime integer not null,
+    position integer not null,
+    primary key (tournament, voter, anime)
+);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+delete from decisions where meeting > 0;
+alter table decisions
+    drop primary key,
+    drop column meeting,
+    add primary key (tournament, left_anime, right_anime, voter);
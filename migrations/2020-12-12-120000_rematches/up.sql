@@ -0,0 +1,8 @@
+-- Your SQL goes here
+
+-- two anime can meet again in a bracket, and each meeting is asked afresh:
+-- 0 the first time they meet, 1 the next
+alter table decisions
+    add column meeting integer not null default 0,
+    drop primary key,
+    add primary key (tournament, left_anime, right_anime, voter, meeting);
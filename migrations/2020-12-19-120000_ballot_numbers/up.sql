@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+-- The ballots from before they were numbered are all 0, so number every
+-- tournament's ballots again from 1: MySQL keeps no record of the order rows
+-- went in, so the unnumbered ones go first in the order of the primary key,
+-- and the rest keep their order after them.
+set @number := 0, @tournament := null;
+update decisions
+set number = (@number := if(tournament <=> @tournament, @number + 1, 1)),
+    tournament = (@tournament := tournament)
+order by tournament, number, left_anime, right_anime, voter, meeting;
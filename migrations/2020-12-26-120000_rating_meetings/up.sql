@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+-- which meeting of the pair each rating came from, as for decisions
+alter table ratings
+    add column meeting integer not null default 0;
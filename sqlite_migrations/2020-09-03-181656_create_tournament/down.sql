@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+drop table anime;
+
+drop table tournaments;
+
+drop table tournament_anime;
+
+drop table decisions;
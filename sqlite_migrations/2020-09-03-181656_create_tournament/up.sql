@@ -0,0 +1,28 @@
+-- Your SQL goes here
+
+create table anime (
+    id integer not null primary key autoincrement,
+    name varchar(255) not null,
+    episodes integer,
+    slot1 boolean not null default 0,
+    slot2 boolean not null default 0,
+    slot3 boolean not null default 0
+);
+
+create table tournaments (
+    id integer not null primary key autoincrement
+);
+
+create table tournament_anime (
+    tournament integer not null,
+    anime integer not null,
+    primary key (tournament, anime)
+);
+
+create table decisions (
+    tournament integer not null,
+    left_anime integer not null,
+    right_anime integer not null,
+    pick boolean,
+    primary key (tournament, left_anime, right_anime)
+);
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+delete from anime;
\ No newline at end of file
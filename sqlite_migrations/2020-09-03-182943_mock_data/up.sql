@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+insert into anime (name, episodes, slot1, slot2, slot3) values
+('Aldnoah.Zero', 24, 1, 0, 0),
+('Bloom Into You', 13, 1, 1, 0),
+('Fairy Gone', 12, 1, 0, 0),
+('Irozuku: The World In Colors', 13, 1, 0, 0),
+('Bounen no Xamdou', 26, 1, 1, 0),
+('Kabaneri of the Iron Fortress', 12, 1, 0, 0),
+('Plastic Memories', 13, 1, 0, 0),
+('Kaguya S2', 12, 0, 0, 1);
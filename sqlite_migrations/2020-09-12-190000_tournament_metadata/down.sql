@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+create table tournaments_old (
+    id integer not null primary key autoincrement
+);
+
+insert into tournaments_old (id) select id from tournaments;
+drop table tournaments;
+alter table tournaments_old rename to tournaments;
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+
+-- sqlite can't add a column with a non-constant default, so rebuild the table
+create table tournaments_new (
+    id integer not null primary key autoincrement,
+    slot integer not null default 1,
+    title varchar(255),
+    status varchar(16) not null default 'open',
+    created_at timestamp not null default current_timestamp,
+    finished_at timestamp
+);
+
+insert into tournaments_new (id) select id from tournaments;
+drop table tournaments;
+alter table tournaments_new rename to tournaments;
+
+-- every pick knocks one anime out, so a tournament is finished once it's down to one
+update tournaments set status = 'finished'
+where (select count(*) from tournament_anime where tournament_anime.tournament = tournaments.id)
+    - (select count(*) from decisions where decisions.tournament = tournaments.id and decisions.pick is not null) <= 1;
@@ -0,0 +1,22 @@
+-- This file should undo anything in `up.sql`
+create table anime_old (
+    id integer not null primary key autoincrement,
+    name varchar(255) not null,
+    episodes integer,
+    slot1 boolean not null default 0,
+    slot2 boolean not null default 0,
+    slot3 boolean not null default 0
+);
+
+insert into anime_old (id, name, episodes, slot1, slot2, slot3)
+select id, name, episodes,
+    id in (select anime from anime_slots where slot = 1),
+    id in (select anime from anime_slots where slot = 2),
+    id in (select anime from anime_slots where slot = 3)
+from anime;
+drop table anime;
+alter table anime_old rename to anime;
+
+drop table anime_slots;
+
+drop table slots;
@@ -0,0 +1,36 @@
+-- Your SQL goes here
+
+create table slots (
+    id integer not null primary key autoincrement,
+    name varchar(255) not null,
+    position integer not null default 0,
+    duration integer,
+    max_episodes integer
+);
+
+create table anime_slots (
+    anime integer not null,
+    slot integer not null,
+    primary key (anime, slot)
+);
+
+-- the three fixed slots keep their numbers, so tournaments.slot still points at the right one
+insert into slots (id, name, position) values
+(1, 'first', 1),
+(2, 'second', 2),
+(3, 'third', 3);
+
+insert into anime_slots (anime, slot) select id, 1 from anime where slot1;
+insert into anime_slots (anime, slot) select id, 2 from anime where slot2;
+insert into anime_slots (anime, slot) select id, 3 from anime where slot3;
+
+-- rebuild the anime table without the slot columns
+create table anime_new (
+    id integer not null primary key autoincrement,
+    name varchar(255) not null,
+    episodes integer
+);
+
+insert into anime_new (id, name, episodes) select id, name, episodes from anime;
+drop table anime;
+alter table anime_new rename to anime;
@@ -0,0 +1,14 @@
+-- This file should undo anything in `up.sql`
+create table tournaments_old (
+    id integer not null primary key autoincrement,
+    slot integer not null default 1,
+    title varchar(255),
+    status varchar(16) not null default 'open',
+    created_at timestamp not null default current_timestamp,
+    finished_at timestamp
+);
+
+insert into tournaments_old (id, slot, title, status, created_at, finished_at)
+select id, slot, title, status, created_at, finished_at from tournaments;
+drop table tournaments;
+alter table tournaments_old rename to tournaments;
@@ -0,0 +1,17 @@
+-- This file should undo anything in `up.sql`
+create table decisions_old (
+    tournament integer not null,
+    left_anime integer not null,
+    right_anime integer not null,
+    pick boolean,
+    primary key (tournament, left_anime, right_anime)
+);
+
+insert into decisions_old (tournament, left_anime, right_anime, pick)
+select tournament, left_anime, right_anime, pick from decisions where voter = 1;
+drop table decisions;
+alter table decisions_old rename to decisions;
+
+drop table tournament_members;
+
+drop table members;
@@ -0,0 +1,32 @@
+-- Your SQL goes here
+
+create table members (
+    id integer not null primary key autoincrement,
+    name varchar(255) not null unique
+);
+
+-- who votes in each tournament
+create table tournament_members (
+    tournament integer not null,
+    member integer not null,
+    primary key (tournament, member)
+);
+
+-- the decisions so far were all made by one person
+insert into members (id, name) values (1, 'me');
+insert into tournament_members (tournament, member) select id, 1 from tournaments;
+
+-- every voter answers the same pairs, so the voter is part of the key
+create table decisions_new (
+    tournament integer not null,
+    left_anime integer not null,
+    right_anime integer not null,
+    pick boolean,
+    voter integer not null default 1,
+    primary key (tournament, left_anime, right_anime, voter)
+);
+
+insert into decisions_new (tournament, left_anime, right_anime, pick, voter)
+select tournament, left_anime, right_anime, pick, 1 from decisions;
+drop table decisions;
+alter table decisions_new rename to decisions;
@@ -0,0 +1,18 @@
+-- This file should undo anything in `up.sql`
+create table tournaments_old (
+    id integer not null primary key autoincrement,
+    slot integer not null default 1,
+    title varchar(255),
+    status varchar(16) not null default 'open',
+    created_at timestamp not null default current_timestamp,
+    finished_at timestamp,
+    mode varchar(16) not null default 'elimination',
+    k integer,
+    pairing varchar(16) not null default 'lowest-pick',
+    rounds integer
+);
+
+insert into tournaments_old (id, slot, title, status, created_at, finished_at, mode, k, pairing, rounds)
+select id, slot, title, status, created_at, finished_at, mode, k, pairing, rounds from tournaments;
+drop table tournaments;
+alter table tournaments_old rename to tournaments;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+-- where a tournament's random choices come from, so it can be replayed;
+-- the tournaments from before use their id
+alter table tournaments
+    add column seed bigint not null default 0;
+
+update tournaments
+    set seed = id;
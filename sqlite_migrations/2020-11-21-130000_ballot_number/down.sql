@@ -0,0 +1,14 @@
+-- This file should undo anything in `up.sql`
+create table decisions_old (
+    tournament integer not null,
+    left_anime integer not null,
+    right_anime integer not null,
+    pick boolean,
+    voter integer not null default 1,
+    primary key (tournament, left_anime, right_anime, voter)
+);
+
+insert into decisions_old (tournament, left_anime, right_anime, pick, voter)
+select tournament, left_anime, right_anime, pick, voter from decisions;
+drop table decisions;
+alter table decisions_old rename to decisions;
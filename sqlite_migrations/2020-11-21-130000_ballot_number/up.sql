@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+-- the order each tournament's ballots were cast in, so the last one can be
+-- taken back; there's no telling for the ballots from before, so they're all 0
+alter table decisions
+    add column number integer not null default 0;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+drop table rooms;
+drop table room_members;
+drop table room_ballots;
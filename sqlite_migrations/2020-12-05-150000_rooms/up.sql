@@ -0,0 +1,30 @@
+-- Your SQL goes here
+
+-- a tournament's crowd voting room: members join it from their phones with its
+-- code, and each matchup is open to their votes for `seconds`
+create table rooms (
+    tournament integer not null primary key,
+    code varchar(8) not null unique,
+    seconds integer not null,
+    matchup integer not null default 1,
+    opened_at timestamp not null default current_timestamp
+);
+
+-- the members who have joined a room
+create table room_members (
+    tournament integer not null,
+    member integer not null,
+    primary key (tournament, member)
+);
+
+-- every vote cast in a room, one per member for each matchup
+create table room_ballots (
+    tournament integer not null,
+    matchup integer not null,
+    voter integer not null,
+    left_anime integer not null,
+    right_anime integer not null,
+    pick boolean,
+    cast_at timestamp not null default current_timestamp,
+    primary key (tournament, matchup, voter)
+);
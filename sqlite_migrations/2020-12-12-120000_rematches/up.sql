@@ -0,0 +1,19 @@
+-- Your SQL goes here
+
+-- two anime can meet again in a bracket, and each meeting is asked afresh:
+-- 0 the first time they meet, 1 the next
+create table decisions_new (
+    tournament integer not null,
+    left_anime integer not null,
+    right_anime integer not null,
+    pick boolean,
+    voter integer not null default 1,
+    number integer not null default 0,
+    meeting integer not null default 0,
+    primary key (tournament, left_anime, right_anime, voter, meeting)
+);
+
+insert into decisions_new (tournament, left_anime, right_anime, pick, voter, number)
+select tournament, left_anime, right_anime, pick, voter, number from decisions;
+drop table decisions;
+alter table decisions_new rename to decisions;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+-- there's no telling which ballots were 0 before, and numbered ones do no harm
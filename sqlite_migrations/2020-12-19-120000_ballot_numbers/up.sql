@@ -0,0 +1,16 @@
+-- Your SQL goes here
+
+-- The ballots from before they were numbered are all 0, so number every
+-- tournament's ballots again from 1: the unnumbered ones first, in the order
+-- they went into the table, then the rest in their order.
+create temporary table ballot_numbers as
+select ballot.rowid as ballot, (
+    select count(*) from decisions earlier
+    where earlier.tournament = ballot.tournament
+        and (earlier.number < ballot.number
+            or (earlier.number = ballot.number and earlier.rowid <= ballot.rowid))
+) as number
+from decisions ballot;
+
+update decisions set number = (select number from ballot_numbers where ballot = decisions.rowid);
+drop table ballot_numbers;
@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+create table ratings_old (
+    id integer not null primary key autoincrement,
+    anime integer not null,
+    system varchar(16) not null,
+    rating double not null,
+    deviation double,
+    volatility double,
+    tournament integer not null,
+    created_at timestamp not null default current_timestamp
+);
+
+insert into ratings_old (id, anime, system, rating, deviation, volatility, tournament, created_at)
+select id, anime, system, rating, deviation, volatility, tournament, created_at from ratings;
+drop table ratings;
+alter table ratings_old rename to ratings;
@@ -0,0 +1,227 @@
+// The JSON API, for programs such as a chat bot to run tournaments with. Each
+// version is mounted under its own prefix, /api/v1 for this one. An error comes
+// back as {"error": "..."} with the matching status code.
+
+use rocket::http::Status as HttpStatus;
+use rocket::request::Request;
+use rocket::response::{self, status::Created, Responder};
+use rocket::serde::json::{self, json, Json, Value};
+use rocket::{Catcher, Route, State};
+use serde::{Deserialize, Serialize};
+
+use tournament::model::{transaction, TournamentError};
+use tournament::model::models::*;
+
+use super::live::Updates;
+use super::{http_status, lock, Store};
+
+pub const PREFIX: &str = "/api/v1";
+
+pub struct ApiError(HttpStatus, String);
+
+impl ApiError {
+    fn bad_request(message: &str) -> ApiError {
+        ApiError(HttpStatus::BadRequest, message.to_string())
+    }
+}
+
+impl From<TournamentError> for ApiError {
+    fn from(err: TournamentError) -> ApiError {
+        ApiError(http_status(&err), err.to_string())
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        (self.0, Json(json!({ "error": self.1 }))).respond_to(request)
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+// a request body, or why it couldn't be read
+type Body<'r, T> = Result<Json<T>, json::Error<'r>>;
+
+fn read_body<T>(body: Body<T>) -> Result<T, ApiError> {
+    body.map(|body| body.into_inner()).map_err(|err| ApiError::bad_request(&err.to_string()))
+}
+
+#[get("/anime")]
+fn list_anime(store: &State<Store>) -> ApiResult<Vec<Anime>> {
+    let model = lock(store);
+    Ok(Json(model.get_anime()?))
+}
+
+#[get("/anime/<id>")]
+fn get_anime(id: i32, store: &State<Store>) -> ApiResult<Anime> {
+    let model = lock(store);
+    Ok(Json(model.get_anime_by_id(id)?))
+}
+
+#[derive(Deserialize)]
+struct AnimeRequest {
+    name: String,
+    episodes: Option<i32>,
+    // slot names or numbers
+    #[serde(default)]
+    slots: Vec<String>,
+}
+
+#[post("/anime", data = "<request>")]
+fn add_anime(request: Body<AnimeRequest>, store: &State<Store>) -> Result<Created<Json<Anime>>, ApiError> {
+    let request = read_body(request)?;
+    let model = lock(store);
+    let mut slot_ids = vec![];
+    for slot in &request.slots {
+        slot_ids.push(model.find_slot(slot)?.id);
+    }
+
+    // the anime and its slots together, so a failure leaves neither
+    let anime = transaction(&**model, || {
+        let anime = model.add_anime(&request.name, request.episodes)?;
+        model.set_anime_slots(anime.id, &slot_ids)?;
+        Ok(anime)
+    })?;
+    Ok(Created::new(format!("{}/anime/{}", PREFIX, anime.id)).body(Json(anime)))
+}
+
+// the same choices as `tournament new`
+#[derive(Deserialize)]
+struct TournamentRequest {
+    // a slot name or number, the first slot if not given
+    slot: Option<String>,
+    title: Option<String>,
+    mode: Option<Mode>,
+    k: Option<i32>,
+    rounds: Option<i32>,
+    pairing: Option<Pairing>,
+    // member names or ids, the first member if none are given
+    #[serde(default)]
+    voters: Vec<String>,
+    // anime ids to seed a bracket with, best first
+    #[serde(default)]
+    seeds: Vec<i32>,
+    random_seed: Option<i64>,
+}
+
+#[post("/tournaments", data = "<request>")]
+fn add_tournament(request: Body<TournamentRequest>, store: &State<Store>) -> Result<Created<Json<Tournament>>, ApiError> {
+    let request = read_body(request)?;
+    let model = lock(store);
+    let defaults = Format::default();
+    let format = Format {
+        mode: request.mode.unwrap_or(defaults.mode),
+        k: request.k,
+        pairing: request.pairing.unwrap_or(defaults.pairing),
+        rounds: request.rounds,
+        seed: request.random_seed,
+    };
+
+    let slot = match &request.slot {
+        Some(slot) => model.find_slot(slot)?,
+        None => model.get_slots()?.into_iter().next()
+            .ok_or_else(|| ApiError::bad_request("there are no slots to run a tournament in"))?
+    };
+    let mut voters = vec![];
+    for voter in &request.voters {
+        voters.push(model.find_member(voter)?);
+    }
+    if voters.is_empty() {
+        voters.extend(model.get_members()?.into_iter().next());
+    }
+    if voters.is_empty() {
+        return Err(ApiError::bad_request("there are no members to vote"));
+    }
+
+    let tournament = model.add_tournament(&slot, request.title.as_deref(), format, &voters, &request.seeds)?;
+    Ok(Created::new(format!("{}/tournaments/{}", PREFIX, tournament.id)).body(Json(tournament)))
+}
+
+#[get("/tournaments/<id>")]
+fn get_tournament(id: i32, store: &State<Store>) -> ApiResult<Tournament> {
+    let model = lock(store);
+    Ok(Json(model.get_tournament(id)?))
+}
+
+#[derive(Serialize)]
+struct Next {
+    // the pair to ask about, or null if there's nothing to ask
+    decision: Option<Decision>,
+    // who still has to answer it
+    waiting: Vec<Member>,
+}
+
+#[get("/tournaments/<id>/next")]
+fn next_decision(id: i32, store: &State<Store>) -> ApiResult<Next> {
+    let model = lock(store);
+    let t = model.get_tournament(id)?;
+    Ok(Json(Next {
+        decision: model.next_tournament_decision(&t)?,
+        waiting: model.waiting_voters(&t)?,
+    }))
+}
+
+#[derive(Deserialize)]
+struct VoteRequest {
+    // a member name or id
+    voter: String,
+    left_anime: i32,
+    right_anime: i32,
+    pick: Pick,
+}
+
+// One voter's answer, returning the tournament as it is afterwards, which may
+// now be finished.
+#[post("/tournaments/<id>/decisions", data = "<request>")]
+fn add_decision(id: i32, request: Body<VoteRequest>, store: &State<Store>, updates: &State<Updates>) -> Result<Created<Json<Tournament>>, ApiError> {
+    let request = read_body(request)?;
+    let model = lock(store);
+    let t = model.get_tournament(id)?;
+    let voter = model.find_member(&request.voter)?;
+
+    model.save_tournament_decision(&t, &voter, Decision {
+        tournament: t.id,
+        left_anime: request.left_anime,
+        right_anime: request.right_anime,
+        pick: Pick::to_opt(request.pick),
+        // the store works out which time they're meeting
+        meeting: 0,
+    })?;
+    updates.notify(id);
+    Ok(Created::new(format!("{}/tournaments/{}", PREFIX, t.id)).body(Json(model.get_tournament(id)?)))
+}
+
+#[derive(Serialize)]
+struct Winner {
+    finished: bool,
+    // null until the tournament is finished, or if it ended in a tie
+    winner: Option<Anime>,
+}
+
+#[get("/tournaments/<id>/winner")]
+fn get_winner(id: i32, store: &State<Store>) -> ApiResult<Winner> {
+    let model = lock(store);
+    let t = model.get_tournament(id)?;
+    let winner = match model.get_tournament_winner(&t)? {
+        Some(anime_id) => Some(model.get_anime_by_id(anime_id)?),
+        None => None
+    };
+    Ok(Json(Winner {
+        finished: model.is_tournament_finished(&t)?,
+        winner,
+    }))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![list_anime, get_anime, add_anime, add_tournament, get_tournament, next_decision, add_decision, get_winner]
+}
+
+// Rocket's own errors, such as a body that isn't valid JSON, in the same shape
+#[catch(default)]
+fn default_catcher(status: HttpStatus, _: &Request) -> (HttpStatus, Json<Value>) {
+    (status, Json(json!({ "error": status.reason().unwrap_or("Unknown error") })))
+}
+
+pub fn catchers() -> Vec<Catcher> {
+    catchers![default_catcher]
+}
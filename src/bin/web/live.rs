@@ -0,0 +1,81 @@
+// The projector view: a read-only page that follows a tournament as the picks
+// come in, sent over server-sent events so nobody has to refresh it.
+//
+// A pick made through this server shows straight away. The page also checks
+// every few seconds, to catch picks made from the command line and to close a
+// room's matchup once its time is up.
+
+use std::time::Duration;
+
+use rocket::http::uri::Host;
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast;
+use rocket::tokio::time::sleep;
+use rocket::{Route, Shutdown, State};
+
+use super::{lock, pages, Page, Store};
+
+// how often to look for picks made elsewhere
+const CHECK_EVERY: Duration = Duration::from_secs(3);
+
+// the ids of tournaments that have just had a pick
+pub struct Updates(broadcast::Sender<i32>);
+
+impl Updates {
+    pub fn new() -> Updates {
+        Updates(broadcast::channel(64).0)
+    }
+
+    pub fn notify(&self, tournament: i32) {
+        // nobody may be watching
+        let _ = self.0.send(tournament);
+    }
+}
+
+#[get("/tournament/<id>/live")]
+fn live(id: i32, store: &State<Store>, host: &Host<'_>) -> Page {
+    let model = lock(store);
+    let t = model.get_tournament(id)?;
+    Ok(pages::live(&t, &pages::live_state(&**model, &t, &host.to_string())?))
+}
+
+// The live page's contents, sent again whenever they change
+#[get("/tournament/<id>/events")]
+fn events<'r>(id: i32, store: &'r State<Store>, updates: &State<Updates>, host: &Host<'_>, mut shutdown: Shutdown) -> EventStream![Event + 'r] {
+    let mut picks = updates.0.subscribe();
+    let host = host.to_string();
+
+    EventStream! {
+        let mut shown = None;
+        loop {
+            let state = {
+                let model = lock(store);
+                model.get_tournament(id).and_then(|t| {
+                    if let Some(room) = model.get_room(&t)? {
+                        model.refresh_room(&room)?;
+                    }
+                    pages::live_state(&**model, &model.get_tournament(id)?, &host)
+                })
+            };
+            let state = match state {
+                Ok(state) => state,
+                Err(err) => pages::alert(&err.to_string()),
+            };
+            if shown.as_ref() != Some(&state) {
+                yield Event::data(state.clone()).event("update");
+                shown = Some(state);
+            }
+
+            select! {
+                _ = picks.recv() => {},
+                _ = sleep(CHECK_EVERY) => {},
+                _ = &mut shutdown => break,
+            }
+        }
+    }
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![live, events]
+}
@@ -0,0 +1,138 @@
+// A web front end for running tournaments: each pair as two buttons, and the
+// winner once the tournament is finished, with a JSON API alongside and rooms
+// for everyone to vote together from their phones. The stylesheet is built
+// separately, with `npm run css`. A release build needs ROCKET_SECRET_KEY set,
+// for the cookies that remember who a phone joined a room as.
+
+#[macro_use]
+extern crate rocket;
+
+mod api;
+mod live;
+mod pages;
+mod room;
+
+use std::process;
+use std::sync::{Mutex, MutexGuard};
+
+use rocket::form::Form;
+use rocket::fs::{relative, FileServer};
+use rocket::http::Status as HttpStatus;
+use rocket::request::Request;
+use rocket::response::{self, content::RawHtml, Redirect, Responder, Response};
+use rocket::State;
+
+use tournament::model::{open_store, TournamentStore, TournamentError};
+use tournament::model::models::*;
+
+// a database connection can only be used by one request at a time
+type Store = Mutex<Box<dyn TournamentStore + Send>>;
+
+fn lock(store: &Store) -> MutexGuard<'_, Box<dyn TournamentStore + Send>> {
+    // a request that panicked leaves nothing half written, so carry on
+    store.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn http_status(err: &TournamentError) -> HttpStatus {
+    match err {
+        TournamentError::NotFound { .. } => HttpStatus::NotFound,
+        TournamentError::Invalid(_) | TournamentError::InvalidDecision(_) => HttpStatus::BadRequest,
+        TournamentError::AlreadyFinished(_) => HttpStatus::Conflict,
+        TournamentError::Connection(_) | TournamentError::Query(_) => HttpStatus::InternalServerError,
+    }
+}
+
+// a tournament error as an error page with the matching status
+struct PageError(TournamentError);
+
+impl From<TournamentError> for PageError {
+    fn from(err: TournamentError) -> PageError {
+        PageError(err)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for PageError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let status = http_status(&self.0);
+        Response::build_from(pages::error(&self.0.to_string()).respond_to(request)?)
+            .status(status)
+            .ok()
+    }
+}
+
+type Page = Result<RawHtml<String>, PageError>;
+
+#[get("/")]
+fn index(store: &State<Store>) -> Page {
+    let model = lock(store);
+    Ok(pages::index(&**model)?)
+}
+
+// The next pair for `voter` to choose between, or for the next voter still to
+// vote if they've had their say. A finished tournament shows its winner.
+#[get("/tournament/<id>?<voter>")]
+fn show_tournament(id: i32, voter: Option<i32>, store: &State<Store>) -> Page {
+    let model = lock(store);
+    let t = model.get_tournament(id)?;
+    if t.status == Status::Abandoned || model.is_tournament_finished(&t)? {
+        return Ok(pages::result(&**model, &t)?);
+    }
+
+    let waiting = model.waiting_voters(&t)?;
+    let voter = waiting.iter()
+        .find(|m| Some(m.id) == voter)
+        .or_else(|| waiting.first());
+    match (model.next_tournament_decision(&t)?, voter) {
+        (Some(decision), Some(voter)) => Ok(pages::decision(&**model, &t, &decision, voter, &waiting)?),
+        _ => Ok(pages::waiting(&t, &waiting))
+    }
+}
+
+#[derive(FromForm)]
+struct Vote {
+    voter: i32,
+    left: i32,
+    right: i32,
+    // "left", "right" or "draw"
+    pick: String,
+}
+
+#[post("/tournament/<id>", data = "<vote>")]
+fn vote(id: i32, vote: Form<Vote>, store: &State<Store>, updates: &State<live::Updates>) -> Result<Redirect, PageError> {
+    let model = lock(store);
+    let t = model.get_tournament(id)?;
+    let voter = model.get_member(vote.voter)?;
+    let pick: Pick = vote.pick.parse().map_err(TournamentError::InvalidDecision)?;
+
+    model.save_tournament_decision(&t, &voter, Decision {
+        tournament: t.id,
+        left_anime: vote.left,
+        right_anime: vote.right,
+        pick: Pick::to_opt(pick),
+        // the store works out which time they're meeting
+        meeting: 0,
+    })?;
+    updates.notify(id);
+    Ok(Redirect::to(uri!(show_tournament(id, _))))
+}
+
+#[launch]
+fn rocket() -> _ {
+    let store = match open_store() {
+        Ok(store) => store,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    };
+
+    rocket::build()
+        .manage(Mutex::new(store))
+        .manage(live::Updates::new())
+        .mount("/", routes![index, show_tournament, vote])
+        .mount("/", live::routes())
+        .mount("/", room::routes())
+        .mount(api::PREFIX, api::routes())
+        .register(api::PREFIX, api::catchers())
+        .mount("/static", FileServer::from(relative!("static")))
+}
@@ -0,0 +1,455 @@
+// The web front end's pages, as plain HTML around Bootstrap's classes.
+
+use chrono::Local;
+use rocket::response::content::RawHtml;
+
+use tournament::model::{Result, TournamentStore, TournamentError};
+use tournament::model::engine::bracket;
+use tournament::model::models::*;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn layout(title: &str, body: &str) -> RawHtml<String> {
+    RawHtml(format!(r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1">
+  <title>{title}</title>
+  <link rel="stylesheet" href="/static/css/style.css">
+</head>
+<body>
+  <nav class="navbar navbar-dark bg-primary mb-4">
+    <a class="navbar-brand" href="/">Tournament</a>
+  </nav>
+  <main class="container">
+{body}
+  </main>
+</body>
+</html>
+"#, title = escape(title), body = body))
+}
+
+pub fn alert(message: &str) -> String {
+    format!(r#"    <div class="alert alert-danger">{}</div>"#, escape(message))
+}
+
+pub fn error(message: &str) -> RawHtml<String> {
+    layout("Error", &format!(r#"{}
+    <a href="/">Back to the tournaments</a>"#, alert(message)))
+}
+
+// tournaments keep their slot's id even if the slot is later removed
+fn slot_name(model: &dyn TournamentStore, slot_id: i32) -> Result<String> {
+    match model.get_slot(slot_id) {
+        Ok(slot) => Ok(slot.name),
+        Err(TournamentError::NotFound { .. }) => Ok(format!("slot #{}", slot_id)),
+        Err(err) => Err(err)
+    }
+}
+
+fn anime_name(model: &dyn TournamentStore, anime_id: i32) -> Result<String> {
+    Ok(escape(&model.get_anime_by_id(anime_id)?.name))
+}
+
+// every tournament, newest first
+pub fn index(model: &dyn TournamentStore) -> Result<RawHtml<String>> {
+    let mut rows = String::new();
+    for t in model.get_tournaments()?.iter().rev() {
+        let badge = match t.status {
+            Status::Open => "badge-success",
+            Status::Finished => "badge-secondary",
+            Status::Abandoned => "badge-light",
+        };
+        rows.push_str(&format!(r#"      <tr>
+        <td><a href="/tournament/{id}">{name}</a></td>
+        <td>{slot}</td>
+        <td>{mode}</td>
+        <td><span class="badge {badge}">{status}</span></td>
+        <td>{created}</td>
+      </tr>
+"#, id = t.id, name = escape(&t.name()), slot = escape(&slot_name(model, t.slot)?), mode = escape(&t.mode_name()),
+            badge = badge, status = t.status.name(), created = t.created_at.format("%Y-%m-%d %H:%M")));
+    }
+
+    if rows.is_empty() {
+        return Ok(layout("Tournaments", r#"    <p class="lead">There are no tournaments yet: start one with <code>tournament new</code>.</p>"#));
+    }
+    Ok(layout("Tournaments", &format!(r#"    <h1>Tournaments</h1>
+    <table class="table table-hover">
+      <thead>
+        <tr><th>Tournament</th><th>Slot</th><th>Mode</th><th>Status</th><th>Started</th></tr>
+      </thead>
+      <tbody>
+{}      </tbody>
+    </table>"#, rows)))
+}
+
+// links to let someone else still waiting vote on the pair instead
+fn other_voters(t: &Tournament, voter: &Member, waiting: &[Member]) -> String {
+    let others: Vec<String> = waiting.iter()
+        .filter(|m| m.id != voter.id)
+        .map(|m| format!(r#"<a href="/tournament/{}?voter={}">{}</a>"#, t.id, m.id, escape(&m.name)))
+        .collect();
+    if others.is_empty() {
+        return String::new();
+    }
+    format!(r#"    <p class="text-muted">Still to vote: {}</p>
+"#, others.join(", "))
+}
+
+// the pair as two big buttons, and a smaller one for a draw
+fn pick_buttons(model: &dyn TournamentStore, decision: &Decision) -> Result<String> {
+    Ok(format!(r#"      <input type="hidden" name="left" value="{left_id}">
+      <input type="hidden" name="right" value="{right_id}">
+      <div class="row">
+        <div class="col-md-6 mb-3">
+          <button type="submit" name="pick" value="left" class="btn btn-primary btn-lg btn-block pick">{left}</button>
+        </div>
+        <div class="col-md-6 mb-3">
+          <button type="submit" name="pick" value="right" class="btn btn-primary btn-lg btn-block pick">{right}</button>
+        </div>
+      </div>
+      <div class="text-center mb-3">
+        <button type="submit" name="pick" value="draw" class="btn btn-outline-secondary">Can't choose</button>
+      </div>
+"#, left_id = decision.left_anime, right_id = decision.right_anime,
+        left = anime_name(model, decision.left_anime)?, right = anime_name(model, decision.right_anime)?))
+}
+
+// the tournament's room, or a form to open one so everyone can vote from
+// their phones
+fn room_link(model: &dyn TournamentStore, t: &Tournament) -> Result<String> {
+    Ok(match model.get_room(t)? {
+        Some(room) => format!(r#"    <p>Room <strong>{}</strong> is open: everyone can vote from their phones at <a href="/join">/join</a>.</p>
+"#, room.code),
+        None => format!(r#"    <form method="post" action="/tournament/{}/room" class="form-inline mb-3">
+      <label class="mr-2" for="seconds">Or let everyone vote from their phones, with</label>
+      <input type="number" min="1" name="seconds" id="seconds" value="30" class="form-control form-control-sm mr-2">
+      <label class="mr-2" for="seconds">seconds for each pair</label>
+      <button type="submit" class="btn btn-sm btn-outline-primary">Open a room</button>
+    </form>
+"#, t.id)
+    })
+}
+
+pub fn decision(model: &dyn TournamentStore, t: &Tournament, decision: &Decision, voter: &Member, waiting: &[Member]) -> Result<RawHtml<String>> {
+    let members = model.get_tournament_members(t)?;
+    let whose = if members.len() > 1 {
+        format!(r#"    <p class="lead">{}'s vote</p>
+"#, escape(&voter.name))
+    } else {
+        String::new()
+    };
+
+    Ok(layout(&t.name(), &format!(r#"    <h1>{name}</h1>
+{whose}    <form method="post" action="/tournament/{id}">
+      <input type="hidden" name="voter" value="{voter}">
+{buttons}    </form>
+{others}{room}    <p><a href="/tournament/{id}/live">Projector view</a></p>"#, name = escape(&t.name()), whose = whose, id = t.id, voter = voter.id,
+        buttons = pick_buttons(model, decision)?, others = other_voters(t, voter, waiting), room = room_link(model, t)?)))
+}
+
+// an unfinished tournament with nothing to ask here: it takes whole ballots,
+// which are filled in from the command line
+pub fn waiting(t: &Tournament, waiting: &[Member]) -> RawHtml<String> {
+    let names: Vec<String> = waiting.iter().map(|m| escape(&m.name)).collect();
+    let message = if t.mode.uses_ballots() {
+        format!("Waiting for {} to fill in their ballots with <code>tournament resume {}</code>.", names.join(", "), t.id)
+    } else {
+        String::from("There's nothing to vote on yet.")
+    };
+    layout(&t.name(), &format!(r#"    <h1>{}</h1>
+    <p class="lead">{}</p>
+    <p><a href="/tournament/{}/live">Projector view</a></p>"#, escape(&t.name()), message, t.id))
+}
+
+fn winner(model: &dyn TournamentStore, anime_id: i32) -> Result<String> {
+    Ok(format!(r#"    <div class="winner">
+      <p class="lead">The winner is</p>
+      <h1 class="display-4">{}</h1>
+    </div>
+"#, anime_name(model, anime_id)?))
+}
+
+// the winner, if there is one, and the anime in order
+pub fn result(model: &dyn TournamentStore, t: &Tournament) -> Result<RawHtml<String>> {
+    let winner = match model.get_tournament_winner(t)? {
+        Some(anime_id) => winner(model, anime_id)?,
+        None if t.status == Status::Abandoned => String::from(r#"    <div class="alert alert-warning">This tournament was abandoned before it was finished.</div>
+"#),
+        None => String::from(r#"    <div class="alert alert-info">No winner: there's a tie for first place.</div>
+"#)
+    };
+
+    let ranking = if t.mode == Mode::TopK {
+        model.get_tournament_top_k(t)?
+    } else {
+        model.get_tournament_ranking(t)?
+    };
+    let mut items = String::new();
+    for (place, anime) in ranking.iter().enumerate() {
+        items.push_str(&format!("      <li class=\"list-group-item\">{}. {}</li>\n", place + 1, escape(&anime.name)));
+    }
+
+    Ok(layout(&t.name(), &format!(r#"    <h1>{name}</h1>
+    <p class="text-muted">{mode}, {status}</p>
+{winner}    <ul class="list-group">
+{items}    </ul>"#, name = escape(&t.name()), mode = escape(&t.mode_name()), status = t.status.name(),
+        winner = winner, items = items)))
+}
+
+// counts each .countdown down to 0 between updates from the server
+const COUNTDOWN: &str = r#"    <script>
+      setInterval(function () {
+        document.querySelectorAll(".countdown").forEach(function (countdown) {
+          countdown.textContent = Math.max(0, countdown.textContent - 1);
+        });
+      }, 1000);
+    </script>"#;
+
+// The projector view, which the events from `live::events` keep up to date
+pub fn live(t: &Tournament, state: &str) -> RawHtml<String> {
+    layout(&t.name(), &format!(r#"    <div id="live" class="live">
+{state}
+    </div>
+    <script>
+      var events = new EventSource("/tournament/{id}/events");
+      events.addEventListener("update", function (event) {{
+        document.getElementById("live").innerHTML = event.data;
+      }});
+    </script>
+{countdown}"#, state = state, id = t.id, countdown = COUNTDOWN))
+}
+
+// Everything the projector shows: the pair being decided, the anime still in
+// and how the tournament has gone so far. `host` is where phones can join the
+// tournament's room, if it has one.
+pub fn live_state(model: &dyn TournamentStore, t: &Tournament, host: &str) -> Result<String> {
+    let mut html = format!(r#"    <h1>{} <small class="text-muted">{}, {}</small></h1>
+"#, escape(&t.name()), escape(&t.mode_name()), t.status.name());
+
+    let finished = model.is_tournament_finished(t)?;
+    match (finished, model.get_tournament_winner(t)?) {
+        (true, Some(anime_id)) => html.push_str(&winner(model, anime_id)?),
+        (true, None) => html.push_str(r#"    <div class="alert alert-info">No winner: there's a tie for first place.</div>
+"#),
+        (false, _) => html.push_str(&matchup(model, t)?)
+    }
+    if let (false, Some(room)) = (finished, model.get_room(t)?) {
+        html.push_str(&room_progress(model, &room, host)?);
+    }
+
+    let progress = if t.is_bracket() {
+        bracket_matches(model, t)?
+    } else if t.mode.uses_ballots() {
+        String::new()
+    } else {
+        decisions(model, t)?
+    };
+    html.push_str(&format!(r#"    <div class="row">
+      <div class="col-md-4">
+{}      </div>
+      <div class="col-md-8">
+{}      </div>
+    </div>
+"#, standing(model, t)?, progress));
+    Ok(html)
+}
+
+// the pair being decided, and who it's waiting for
+fn matchup(model: &dyn TournamentStore, t: &Tournament) -> Result<String> {
+    let waiting: Vec<String> = model.waiting_voters(t)?.iter().map(|m| escape(&m.name)).collect();
+    let waiting = if waiting.is_empty() {
+        String::new()
+    } else {
+        format!(r#"      <p class="text-muted">Waiting for {}</p>
+"#, waiting.join(", "))
+    };
+
+    Ok(match model.next_tournament_decision(t)? {
+        Some(decision) => format!(r#"    <div class="matchup">
+      <div class="row align-items-center">
+        <div class="col-5 matchup-anime">{}</div>
+        <div class="col-2 matchup-vs">vs</div>
+        <div class="col-5 matchup-anime">{}</div>
+      </div>
+{}    </div>
+"#, anime_name(model, decision.left_anime)?, anime_name(model, decision.right_anime)?, waiting),
+        None => format!(r#"    <div class="matchup">
+      <p class="lead">Filling in ballots</p>
+{}    </div>
+"#, waiting)
+    })
+}
+
+fn seconds_left(room: &Room) -> i64 {
+    (room.closes_at() - Local::now().naive_local()).num_seconds().max(0)
+}
+
+// how to join the room, and how the vote on its open matchup is going
+fn room_progress(model: &dyn TournamentStore, room: &Room, host: &str) -> Result<String> {
+    let members = model.get_room_members(room)?;
+    let votes = model.get_room_ballots(room)?.iter().filter(|ballot| ballot.matchup == room.matchup).count();
+    Ok(format!(r#"    <div class="alert alert-primary">
+      Vote at <strong>{host}/join</strong> with the code <strong class="room-code">{code}</strong>
+      <span class="float-right">{votes} of {members} voted, <span class="countdown">{left}</span>s left</span>
+    </div>
+"#, host = escape(host), code = room.code, votes = votes, members = members.len(), left = seconds_left(room)))
+}
+
+// A phone's way into a room, with the code from the projector
+pub fn join() -> RawHtml<String> {
+    layout("Join a room", r#"    <h1>Join a room</h1>
+    <form method="get" action="/join">
+      <div class="form-group">
+        <label for="code">Room code</label>
+        <input type="text" name="code" id="code" class="form-control form-control-lg room-code" autocomplete="off" autocapitalize="characters" required autofocus>
+      </div>
+      <button type="submit" class="btn btn-primary btn-lg btn-block">Join</button>
+    </form>"#)
+}
+
+// joining a room, as a member or a new one by name
+pub fn room_join(t: &Tournament, room: &Room) -> RawHtml<String> {
+    layout(&t.name(), &format!(r#"    <h1>{name}</h1>
+    <form method="post" action="/room/{code}/join">
+      <div class="form-group">
+        <label for="name">Your name</label>
+        <input type="text" name="name" id="name" class="form-control form-control-lg" required autofocus>
+      </div>
+      <button type="submit" class="btn btn-primary btn-lg btn-block">Join room {code}</button>
+    </form>"#, name = escape(&t.name()), code = room.code))
+}
+
+// A phone in a room: the open matchup as two buttons, or what it's waiting
+// for. `state` is the room's state as `room::state` gives it, which the page
+// checks every couple of seconds, so it moves on when the matchup closes.
+pub fn room_vote(model: &dyn TournamentStore, t: &Tournament, room: &Room, member: &Member, state: &str) -> Result<RawHtml<String>> {
+    let voted = model.get_room_ballots(room)?.iter().any(|ballot| ballot.matchup == room.matchup && ballot.voter == member.id);
+    let body = if model.is_tournament_finished(t)? {
+        match model.get_tournament_winner(t)? {
+            Some(anime_id) => winner(model, anime_id)?,
+            None => String::from(r#"    <div class="alert alert-info">No winner: there's a tie for first place.</div>
+"#)
+        }
+    } else {
+        match model.next_tournament_decision(t)? {
+            Some(_) if voted => format!(r#"    <p class="lead">Your vote's in. Waiting for everyone else, <span class="countdown">{}</span>s left.</p>
+"#, seconds_left(room)),
+            Some(decision) => format!(r#"    <form method="post" action="/room/{code}">
+{buttons}    </form>
+    <p class="text-center text-muted"><span class="countdown">{left}</span>s left</p>
+"#, code = room.code, buttons = pick_buttons(model, &decision)?, left = seconds_left(room)),
+            None => String::from(r#"    <p class="lead">Waiting for the next pair.</p>
+"#)
+        }
+    };
+
+    Ok(layout(&t.name(), &format!(r#"    <h1>{name}</h1>
+    <p class="text-muted">In room {code} as {member}</p>
+{body}    <script>
+      setInterval(function () {{
+        fetch("/room/{code}/state")
+          .then(function (response) {{ return response.text(); }})
+          .then(function (state) {{ if (state !== "{state}") location.reload(); }});
+      }}, 2000);
+    </script>
+{countdown}"#, name = escape(&t.name()), code = room.code, member = escape(&member.name), body = body,
+        state = escape(state), countdown = COUNTDOWN)))
+}
+
+// the anime still in, with those that are out struck through, or the ranking
+// so far where no anime go out
+fn standing(model: &dyn TournamentStore, t: &Tournament) -> Result<String> {
+    let mut items = String::new();
+    if t.mode.knocks_out() {
+        let remaining = model.tournament_remaining_anime(t)?;
+        let mut anime = model.get_tournament_anime(t)?;
+        anime.sort_by_key(|a| !remaining.contains(&a.id));
+        for a in &anime {
+            if remaining.contains(&a.id) {
+                items.push_str(&format!("          <li class=\"list-group-item\">{}</li>\n", escape(&a.name)));
+            } else {
+                items.push_str(&format!("          <li class=\"list-group-item text-muted\"><del>{}</del></li>\n", escape(&a.name)));
+            }
+        }
+        return Ok(format!(r#"        <h2>Still in</h2>
+        <ul class="list-group mb-3">
+{}        </ul>
+"#, items));
+    }
+
+    for (place, a) in model.get_tournament_ranking(t)?.iter().enumerate() {
+        items.push_str(&format!("          <li class=\"list-group-item\">{}. {}</li>\n", place + 1, escape(&a.name)));
+    }
+    Ok(format!(r#"        <h2>Ranking so far</h2>
+        <ul class="list-group mb-3">
+{}        </ul>
+"#, items))
+}
+
+// the bracket's matches round by round, with their winners as they're known
+fn bracket_matches(model: &dyn TournamentStore, t: &Tournament) -> Result<String> {
+    let matches = model.get_bracket_matches(t)?;
+    let decisions = model.get_tournament_decisions(t)?;
+    let played = bracket::play(&matches, &decisions);
+
+    // an anime's name once it's known, or where it will come from
+    let side = |anime: Option<i32>, source: Source, winner: Option<i32>| -> Result<String> {
+        Ok(match (anime, source) {
+            (Some(anime_id), _) if Some(anime_id) == winner => format!("<strong>{}</strong>", anime_name(model, anime_id)?),
+            (Some(anime_id), _) => anime_name(model, anime_id)?,
+            (None, Source::Winner(number)) => format!(r#"<span class="text-muted">winner of #{}</span>"#, number),
+            (None, Source::Loser(number)) => format!(r#"<span class="text-muted">loser of #{}</span>"#, number),
+            (None, Source::Anime(anime_id)) => anime_name(model, anime_id)?,
+        })
+    };
+
+    let mut html = String::new();
+    let mut heading = None;
+    for (m, (left, right, winner)) in matches.iter().zip(played.iter()) {
+        if heading != Some((m.bracket, m.round)) {
+            if heading.is_some() {
+                html.push_str("        </ul>\n");
+            }
+            let title = match m.bracket {
+                Bracket::Winners => format!("Winners' bracket, round {}", m.round),
+                Bracket::Losers => format!("Losers' bracket, round {}", m.round),
+                Bracket::Final => String::from("Final"),
+            };
+            html.push_str(&format!("        <h3>{}</h3>\n        <ul class=\"list-unstyled\">\n", title));
+            heading = Some((m.bracket, m.round));
+        }
+        html.push_str(&format!("          <li>#{} {} vs {}</li>\n", m.number, side(*left, m.left(), *winner)?, side(*right, m.right(), *winner)?));
+    }
+    if heading.is_some() {
+        html.push_str("        </ul>\n");
+    }
+    Ok(html)
+}
+
+// the pairs decided so far, most recent first
+fn decisions(model: &dyn TournamentStore, t: &Tournament) -> Result<String> {
+    let mut items = String::new();
+    for decision in model.get_tournament_decisions(t)?.iter().rev() {
+        let left = anime_name(model, decision.left_anime)?;
+        let right = anime_name(model, decision.right_anime)?;
+        let result = match Pick::to_pick(decision.pick) {
+            Pick::Left => format!("<strong>{}</strong> beat {}", left, right),
+            Pick::Right => format!("<strong>{}</strong> beat {}", right, left),
+            Pick::Undecided => format!("{} drew with {}", left, right),
+        };
+        items.push_str(&format!("          <li>{}</li>\n", result));
+    }
+    Ok(format!(r#"        <h2>Decisions</h2>
+        <ul class="list-unstyled">
+{}        </ul>
+"#, items))
+}
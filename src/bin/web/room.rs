@@ -0,0 +1,153 @@
+// Crowd voting: a tournament's room, which members join from their phones with
+// its code to vote on each pair together. The majority decides the pair once
+// everyone in the room has voted, or when its time is up. The projector view
+// shows the code, and how the vote is going.
+//
+// A phone remembers who it joined as in a private cookie, which can't be read
+// or forged without the server's secret key.
+
+use rocket::form::Form;
+use rocket::http::{Cookie, CookieJar};
+use rocket::response::{content::RawHtml, Redirect};
+use rocket::{Route, State};
+
+use tournament::model::{Result, TournamentStore, TournamentError};
+use tournament::model::models::*;
+
+use super::live::Updates;
+use super::{lock, pages, Page, PageError, Store};
+
+// the member a phone joined as, encrypted
+const MEMBER_COOKIE: &str = "member";
+
+// the member this phone has joined a room as before, if it has
+fn phone_member(model: &dyn TournamentStore, cookies: &CookieJar<'_>) -> Result<Option<Member>> {
+    let member_id = cookies.get_private(MEMBER_COOKIE).and_then(|cookie| cookie.value().parse::<i32>().ok());
+    Ok(model.get_members()?.into_iter().find(|m| Some(m.id) == member_id))
+}
+
+#[derive(FromForm)]
+struct OpenRoom {
+    seconds: i32,
+}
+
+#[post("/tournament/<id>/room", data = "<form>")]
+fn open(id: i32, form: Form<OpenRoom>, store: &State<Store>) -> std::result::Result<Redirect, PageError> {
+    let model = lock(store);
+    let t = model.get_tournament(id)?;
+    model.open_room(&t, form.seconds)?;
+    Ok(Redirect::to(format!("/tournament/{}/live", id)))
+}
+
+#[get("/join?<code>")]
+fn join_code(code: &str) -> Redirect {
+    match code.trim() {
+        "" => Redirect::to("/join"),
+        code => Redirect::to(format!("/room/{}", code.to_uppercase()))
+    }
+}
+
+#[get("/join", rank = 2)]
+fn join() -> RawHtml<String> {
+    pages::join()
+}
+
+// codes are shown in capitals, but can be typed either way
+fn find_room(model: &dyn TournamentStore, code: &str) -> Result<Room> {
+    model.find_room(&code.to_uppercase())
+}
+
+// the room as it is now, letting the projector know if a matchup just closed
+fn refresh(model: &dyn TournamentStore, room: &Room, updates: &Updates) -> Result<Room> {
+    let refreshed = model.refresh_room(room)?;
+    if refreshed.matchup != room.matchup {
+        updates.notify(room.tournament);
+    }
+    Ok(refreshed)
+}
+
+// the member this phone joined the room as, if it has
+fn joined(model: &dyn TournamentStore, room: &Room, cookies: &CookieJar<'_>) -> Result<Option<Member>> {
+    let member = match phone_member(model, cookies)? {
+        Some(member) => member,
+        None => return Ok(None)
+    };
+    Ok(model.get_room_members(room)?.into_iter().find(|m| m.id == member.id))
+}
+
+// what a phone in the room checks for, to know when to show the next pair
+fn state(room: &Room, t: &Tournament) -> String {
+    format!("{} {}", room.matchup, t.status.name())
+}
+
+#[get("/room/<code>")]
+fn show_room(code: &str, store: &State<Store>, updates: &State<Updates>, cookies: &CookieJar<'_>) -> Page {
+    let model = lock(store);
+    let room = refresh(&**model, &find_room(&**model, code)?, updates)?;
+    let t = model.get_tournament(room.tournament)?;
+    match joined(&**model, &room, cookies)? {
+        Some(member) => Ok(pages::room_vote(&**model, &t, &room, &member, &state(&room, &t))?),
+        None => Ok(pages::room_join(&t, &room))
+    }
+}
+
+#[get("/room/<code>/state")]
+fn room_state(code: &str, store: &State<Store>, updates: &State<Updates>) -> std::result::Result<String, PageError> {
+    let model = lock(store);
+    let room = refresh(&**model, &find_room(&**model, code)?, updates)?;
+    Ok(state(&room, &model.get_tournament(room.tournament)?))
+}
+
+#[derive(FromForm)]
+struct JoinRoom {
+    name: String,
+}
+
+// Join as a new member with that name. A name that's taken can only be joined
+// as from a phone that has joined as it before.
+#[post("/room/<code>/join", data = "<form>")]
+fn join_room(code: &str, form: Form<JoinRoom>, store: &State<Store>, updates: &State<Updates>, cookies: &CookieJar<'_>) -> std::result::Result<Redirect, PageError> {
+    let model = lock(store);
+    let room = find_room(&**model, code)?;
+    let name = form.name.trim();
+    if name.is_empty() {
+        return Err(TournamentError::Invalid("a name is needed to join a room".to_string()).into());
+    }
+
+    let member = match phone_member(&**model, cookies)? {
+        Some(member) if member.name.eq_ignore_ascii_case(name) => member,
+        _ if model.get_members()?.iter().any(|m| m.name.eq_ignore_ascii_case(name)) => {
+            return Err(TournamentError::Invalid(format!("{} is taken, so pick another name", name)).into());
+        },
+        _ => model.add_member(name)?
+    };
+    model.join_room(&room, &member)?;
+    cookies.add_private(Cookie::new(MEMBER_COOKIE, member.id.to_string()));
+    updates.notify(room.tournament);
+    Ok(Redirect::to(format!("/room/{}", room.code)))
+}
+
+#[derive(FromForm)]
+struct RoomVote {
+    left: i32,
+    right: i32,
+    // "left", "right" or "draw"
+    pick: String,
+}
+
+#[post("/room/<code>", data = "<vote>")]
+fn vote(code: &str, vote: Form<RoomVote>, store: &State<Store>, updates: &State<Updates>, cookies: &CookieJar<'_>) -> std::result::Result<Redirect, PageError> {
+    let model = lock(store);
+    let room = find_room(&**model, code)?;
+    let member = joined(&**model, &room, cookies)?
+        .ok_or_else(|| TournamentError::InvalidDecision(format!("join room {} to vote in it", room.code)))?;
+    let pick: Pick = vote.pick.parse().map_err(TournamentError::InvalidDecision)?;
+
+    model.cast_room_ballot(&room, &member, vote.left, vote.right, pick)?;
+    updates.notify(room.tournament);
+    Ok(Redirect::to(format!("/room/{}", room.code)))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![open, join_code, join, show_room, room_state, join_room, vote]
+}
@@ -0,0 +1,5 @@
+#[macro_use]
+extern crate diesel;
+extern crate dotenv;
+
+pub mod model;
@@ -1,69 +1,1048 @@
-#[macro_use]
-extern crate diesel;
-extern crate dotenv;
+use std::error::Error;
+use std::process;
 
-mod model;
+use tournament::model::{open_store, transaction, TournamentStore, TournamentError};
+use tournament::model::models::*;
+use std::cmp::Ordering;
 
-use model::*;
-use model::models::*;
-// use diesel::prelude::*;
+use tournament::model::engine::{self, bracket};
+use tournament::model::engine::votes::same_decision;
+use tournament::model::results::{self, Method};
 
-/*
-mod model;
-use model::*;
+use dialoguer::{Input, MultiSelect, Select, Sort};
+use structopt::StructOpt;
 
-mod mock;
-use mock::*;
-*/
+#[derive(StructOpt)]
+#[structopt(name = "tournament", about = "Choose the next anime to watch, one pair at a time")]
+struct Opt {
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Add, list, edit or remove anime
+    Anime(AnimeCommand),
+    /// Add, list, edit or remove showing slots
+    Slot(SlotCommand),
+    /// Add or list the members who vote
+    Member(MemberCommand),
+    /// Show the anime's ratings from all the tournaments
+    Rating(RatingCommand),
+    /// Start, list, resume or show tournaments
+    Tournament(TournamentCommand),
+    /// Show the winner of a tournament
+    Winner {
+        id: i32,
+    },
+    /// Show the full ranking from a tournament
+    Ranking {
+        id: i32,
+    },
+    /// Show the top picks from a top-k tournament
+    Top {
+        id: i32,
+    },
+    /// Show the standings from a swiss or round-robin tournament
+    Standings {
+        id: i32,
+    },
+    /// Fit a Bradley-Terry strength to each anime from the decisions in some
+    /// tournaments (all of them if none are given)
+    Strength {
+        ids: Vec<i32>,
+    },
+    /// Count every member's votes in a tournament and say if there's a
+    /// Condorcet winner
+    Results {
+        id: i32,
+        /// "schulze", "copeland" or "ranked-pairs"
+        #[structopt(long, default_value = "schulze")]
+        method: Method,
+    },
+}
+
+#[derive(StructOpt)]
+enum AnimeCommand {
+    /// Add an anime to the list
+    Add {
+        name: String,
+        #[structopt(long)]
+        episodes: Option<i32>,
+        /// A slot it can be shown in, by name or number (repeat for several)
+        #[structopt(long = "slot")]
+        slots: Vec<String>,
+    },
+    /// List the anime, optionally only those for one slot
+    List {
+        #[structopt(long)]
+        slot: Option<String>,
+    },
+    /// Change an anime's name, episodes or slots
+    Edit {
+        id: i32,
+        #[structopt(long)]
+        name: Option<String>,
+        #[structopt(long)]
+        episodes: Option<i32>,
+        /// Replace its slots with these (repeat for several)
+        #[structopt(long = "slot")]
+        slots: Vec<String>,
+    },
+    /// Remove an anime from the list, if it isn't in any tournament
+    Remove {
+        id: i32,
+    },
+}
+
+#[derive(StructOpt)]
+enum SlotCommand {
+    /// Add a showing slot
+    Add {
+        name: String,
+        /// Where it comes in the meeting: 1 for the first slot
+        #[structopt(long)]
+        position: i32,
+        /// Typical length in minutes
+        #[structopt(long)]
+        duration: Option<i32>,
+        #[structopt(long)]
+        max_episodes: Option<i32>,
+    },
+    /// List the slots in showing order
+    List,
+    /// Change a slot, given by name or number
+    Edit {
+        slot: String,
+        #[structopt(long)]
+        name: Option<String>,
+        #[structopt(long)]
+        position: Option<i32>,
+        #[structopt(long)]
+        duration: Option<i32>,
+        #[structopt(long)]
+        max_episodes: Option<i32>,
+    },
+    /// Remove a slot, given by name or number
+    Remove {
+        slot: String,
+    },
+}
+
+#[derive(StructOpt)]
+enum MemberCommand {
+    /// Add a member who can vote in tournaments
+    Add {
+        name: String,
+    },
+    /// List the members
+    List,
+}
+
+#[derive(StructOpt)]
+enum RatingCommand {
+    /// List every rated anime, best first
+    Leaderboard {
+        /// "elo" or "glicko2"
+        #[structopt(long, default_value = "elo")]
+        system: System,
+    },
+    /// Show how an anime's rating has changed
+    History {
+        id: i32,
+        /// "elo" or "glicko2"
+        #[structopt(long, default_value = "elo")]
+        system: System,
+    },
+    /// Rate all the decisions again from the start
+    Rebuild,
+}
 
-use dialoguer::Input;
+#[derive(StructOpt)]
+enum TournamentCommand {
+    /// Start a new tournament with all the anime for a slot
+    New {
+        /// The slot, by name or number
+        #[structopt(long, default_value = "1")]
+        slot: String,
+        #[structopt(long)]
+        title: Option<String>,
+        /// "elimination" to find a winner, "ranking" to put them all in order,
+        /// "top-k" to find the best few, "single-elimination" or
+        /// "double-elimination" to play a seeded bracket, "swiss" to play a
+        /// few rounds of anime on the same score, "round-robin" to compare
+        /// every pair, or "approval", "borda" or "instant-runoff" for each
+        /// voter to fill in one whole ballot: ticking every anime they'd
+        /// watch, or putting them all in order
+        #[structopt(long, default_value = "elimination")]
+        mode: Mode,
+        /// How many anime a top-k tournament picks
+        #[structopt(long)]
+        k: Option<i32>,
+        /// How many rounds a swiss tournament plays (enough to find a clear
+        /// winner if not given)
+        #[structopt(long)]
+        rounds: Option<i32>,
+        /// How an elimination tournament picks its next pair: "lowest-pick",
+        /// "bracket", "swiss" or "info-gain"
+        #[structopt(long, default_value = "lowest-pick")]
+        pairing: Pairing,
+        /// A member who votes, by name or id (repeat for several; the first
+        /// member if none are given)
+        #[structopt(long = "voter")]
+        voters: Vec<String>,
+        /// An anime id to seed a bracket with, best first (repeat for several;
+        /// the rest are seeded by rating)
+        #[structopt(long = "seed")]
+        seeds: Vec<i32>,
+        /// Make the same random choices as an earlier tournament with this
+        /// random seed, as shown by `tournament show` (the same answers get
+        /// the same questions as long as the anime's ratings are the same)
+        #[structopt(long)]
+        random_seed: Option<i64>,
+    },
+    /// List all the tournaments
+    List,
+    /// Carry on with an unfinished tournament
+    Resume {
+        id: i32,
+        /// Only ask these members (repeat for several; everyone if none are given)
+        #[structopt(long = "voter")]
+        voters: Vec<String>,
+    },
+    /// Show a tournament's anime and decisions
+    Show {
+        id: i32,
+    },
+    /// Work out a tournament's questions again from its random seed and
+    /// answers, in the order they were asked
+    Replay {
+        id: i32,
+    },
+    /// Show a bracket tournament's matches, round by round
+    Bracket {
+        id: i32,
+    },
+    /// Take back the last vote in a tournament
+    Undo {
+        id: i32,
+    },
+    /// Change a vote in a tournament, even once it's finished
+    Amend {
+        id: i32,
+        /// The anime on the left
+        left: i32,
+        /// The anime on the right
+        right: i32,
+        /// "left", "right" or "draw"
+        pick: Pick,
+        /// Whose vote to change, by name or id (the first member if not given)
+        #[structopt(long)]
+        voter: Option<String>,
+    },
+    /// Give up on an unfinished tournament
+    Abandon {
+        id: i32,
+    },
+}
 
 
 fn main() {
-    let model = Model::connect();
-    
-    // let items = model.get_anime_for_slot(Slot::First);
-    let tournament = model.add_tournament(Slot::First);
-    let items = model.get_tournament_anime(&tournament);
-    println!("Tournament #{} has {} anime for {} slot", tournament.id, items.len(), "first");
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let opt = Opt::from_args();
+    let model = open_store()?;
+    let model = &*model;
+
+    match opt.command {
+        None => {
+            let tournament = choose_tournament(model)?;
+            run_tournament(model, &tournament, &[])
+        },
+        Some(Command::Anime(command)) => run_anime_command(model, command),
+        Some(Command::Slot(command)) => run_slot_command(model, command),
+        Some(Command::Member(command)) => run_member_command(model, command),
+        Some(Command::Rating(command)) => run_rating_command(model, command),
+        Some(Command::Tournament(command)) => run_tournament_command(model, command),
+        Some(Command::Winner { id }) => {
+            let tournament = model.get_tournament(id)?;
+            print_winner(model, &tournament)
+        },
+        Some(Command::Ranking { id }) => {
+            let tournament = model.get_tournament(id)?;
+            print_ranking(model, &tournament)
+        },
+        Some(Command::Top { id }) => {
+            let tournament = model.get_tournament(id)?;
+            print_top_k(model, &tournament)
+        },
+        Some(Command::Standings { id }) => {
+            let tournament = model.get_tournament(id)?;
+            print_standings(model, &tournament)
+        },
+        Some(Command::Strength { ids }) => {
+            let tournaments = if ids.is_empty() {
+                model.get_tournaments()?
+            } else {
+                ids.iter().map(|id| model.get_tournament(*id)).collect::<Result<Vec<_>, _>>()?
+            };
+            print_strength(model, &tournaments)
+        },
+        Some(Command::Results { id, method }) => {
+            let tournament = model.get_tournament(id)?;
+            print_results(model, &tournament, method)
+        },
+    }
+}
+
+fn run_anime_command(model: &dyn TournamentStore, command: AnimeCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        AnimeCommand::Add { name, episodes, slots } => {
+            let slot_ids = find_slot_ids(model, &slots)?;
+            let anime = transaction(model, || {
+                let anime = model.add_anime(&name, episodes)?;
+                model.set_anime_slots(anime.id, &slot_ids)?;
+                Ok(anime)
+            })?;
+            println!("Added anime #{}: {}", anime.id, anime.name);
+        },
+        AnimeCommand::List { slot } => {
+            let anime = match slot {
+                Some(slot) => model.get_anime_for_slot(&model.find_slot(&slot)?)?,
+                None => model.get_anime()?
+            };
+            for a in &anime {
+                print_anime(model, a)?;
+            }
+        },
+        AnimeCommand::Edit { id, name, episodes, slots } => {
+            let mut anime = model.get_anime_by_id(id)?;
+            if let Some(name) = name {
+                anime.name = name;
+            }
+            if episodes.is_some() {
+                anime.episodes = episodes;
+            }
+            model.update_anime(&anime)?;
+            if !slots.is_empty() {
+                model.set_anime_slots(anime.id, &find_slot_ids(model, &slots)?)?;
+            }
+            print_anime(model, &anime)?;
+        },
+        AnimeCommand::Remove { id } => {
+            let anime = model.get_anime_by_id(id)?;
+            model.remove_anime(id)?;
+            println!("Removed anime #{}: {}", anime.id, anime.name);
+        },
+    }
+    Ok(())
+}
+
+fn run_slot_command(model: &dyn TournamentStore, command: SlotCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        SlotCommand::Add { name, position, duration, max_episodes } => {
+            let slot = model.add_slot(&NewSlot { name, position, duration, max_episodes })?;
+            println!("Added slot #{}: {}", slot.id, slot.name);
+        },
+        SlotCommand::List => {
+            for slot in model.get_slots()? {
+                print_slot(&slot);
+            }
+        },
+        SlotCommand::Edit { slot, name, position, duration, max_episodes } => {
+            let mut slot = model.find_slot(&slot)?;
+            if let Some(name) = name {
+                slot.name = name;
+            }
+            if let Some(position) = position {
+                slot.position = position;
+            }
+            if duration.is_some() {
+                slot.duration = duration;
+            }
+            if max_episodes.is_some() {
+                slot.max_episodes = max_episodes;
+            }
+            model.update_slot(&slot)?;
+            print_slot(&slot);
+        },
+        SlotCommand::Remove { slot } => {
+            let slot = model.find_slot(&slot)?;
+            model.remove_slot(slot.id)?;
+            println!("Removed slot #{}: {}", slot.id, slot.name);
+        },
+    }
+    Ok(())
+}
+
+fn run_member_command(model: &dyn TournamentStore, command: MemberCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        MemberCommand::Add { name } => {
+            let member = model.add_member(&name)?;
+            println!("Added member #{}: {}", member.id, member.name);
+        },
+        MemberCommand::List => {
+            for member in model.get_members()? {
+                println!("#{:<4} {}", member.id, member.name);
+            }
+        },
+    }
+    Ok(())
+}
+
+fn run_rating_command(model: &dyn TournamentStore, command: RatingCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        RatingCommand::Leaderboard { system } => {
+            for (place, rating) in model.get_leaderboard(system)?.iter().enumerate() {
+                // rated before it was removed from the list
+                let name = match model.get_anime_by_id(rating.anime) {
+                    Ok(anime) => anime.name,
+                    Err(TournamentError::NotFound { .. }) => format!("anime #{} (removed)", rating.anime),
+                    Err(err) => return Err(err.into())
+                };
+                println!("{:>3}. {:<40} {}", place + 1, name, format_rating(rating));
+            }
+        },
+        RatingCommand::History { id, system } => {
+            let anime = model.get_anime_by_id(id)?;
+            println!("{} ({}):", anime.name, system.name());
+            for rating in model.get_rating_history(id, system)? {
+                println!("  {}  tournament #{:<4} {}", rating.created_at.format("%Y-%m-%d %H:%M"), rating.tournament, format_rating(&rating));
+            }
+        },
+        RatingCommand::Rebuild => {
+            model.rebuild_ratings()?;
+            println!("Rated {} anime", model.get_leaderboard(System::Elo)?.len());
+        },
+    }
+    Ok(())
+}
+
+fn format_rating(rating: &Rating) -> String {
+    match rating.deviation {
+        Some(deviation) => format!("{:>6.0} ± {:.0}", rating.rating, deviation),
+        None => format!("{:>6.0}", rating.rating)
+    }
+}
+
+fn find_members(model: &dyn TournamentStore, names: &[String]) -> Result<Vec<Member>, Box<dyn Error>> {
+    let mut members = vec![];
+    for name in names {
+        members.push(model.find_member(name)?);
+    }
+    Ok(members)
+}
+
+// the members given, or the first member on their own
+fn find_voters(model: &dyn TournamentStore, names: &[String]) -> Result<Vec<Member>, Box<dyn Error>> {
+    if !names.is_empty() {
+        return find_members(model, names);
+    }
+    let first = model.get_members()?.into_iter().next().ok_or("There are no members to vote")?;
+    Ok(vec![first])
+}
+
+fn find_slot_ids(model: &dyn TournamentStore, slots: &[String]) -> Result<Vec<i32>, Box<dyn Error>> {
+    let mut slot_ids = vec![];
+    for slot in slots {
+        slot_ids.push(model.find_slot(slot)?.id);
+    }
+    Ok(slot_ids)
+}
+
+fn run_tournament_command(model: &dyn TournamentStore, command: TournamentCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        TournamentCommand::New { slot, title, mode, k, rounds, pairing, voters, seeds, random_seed } => {
+            let slot = model.find_slot(&slot)?;
+            let voters = find_voters(model, &voters)?;
+            let tournament = model.add_tournament(&slot, title.as_deref(), Format { mode, k, pairing, rounds, seed: random_seed }, &voters, &seeds)?;
+            let items = model.get_tournament_anime(&tournament)?;
+            println!("Tournament #{} has {} anime for {} slot", tournament.id, items.len(), slot.name);
+            run_tournament(model, &tournament, &[])
+        },
+        TournamentCommand::List => {
+            for t in model.get_tournaments()? {
+                let anime = model.get_tournament_anime(&t)?;
+                let remaining = model.tournament_remaining_anime(&t)?;
+                let left = if !t.mode.knocks_out() {
+                    format!("{} anime", anime.len())
+                } else {
+                    format!("{} of {} anime left", remaining.len(), anime.len())
+                };
+                println!("{:<30} {:<10} {:<18} {:<9}  {}  {}",
+                    t.name(), slot_name(model, t.slot)?, t.mode_name(), t.status.name(), t.created_at.format("%Y-%m-%d %H:%M"), left);
+            }
+            Ok(())
+        },
+        TournamentCommand::Resume { id, voters } => {
+            let mut tournament = model.get_tournament(id)?;
+            let voters = find_members(model, &voters)?;
+            if tournament.status == Status::Abandoned {
+                tournament = model.reopen_tournament(&tournament)?;
+            }
+            run_tournament(model, &tournament, &voters)
+        },
+        TournamentCommand::Show { id } => {
+            let tournament = model.get_tournament(id)?;
+            show_tournament(model, &tournament)
+        },
+        TournamentCommand::Replay { id } => {
+            let tournament = model.get_tournament(id)?;
+            println!("Tournament {} with random seed {}:", tournament.name(), tournament.seed);
+            for (number, decision) in model.replay_tournament(&tournament)?.iter().enumerate() {
+                let left = model.get_anime_by_id(decision.left_anime)?;
+                let right = model.get_anime_by_id(decision.right_anime)?;
+                println!("{:>3}. {} vs {} => {}", number + 1, left.name, right.name, Pick::to_pick(decision.pick).name());
+            }
+            Ok(())
+        },
+        TournamentCommand::Bracket { id } => {
+            let tournament = model.get_tournament(id)?;
+            show_bracket(model, &tournament)
+        },
+        TournamentCommand::Undo { id } => {
+            let tournament = model.get_tournament(id)?;
+            undo_decision(model, &tournament)
+        },
+        TournamentCommand::Amend { id, left, right, pick, voter } => {
+            let tournament = model.get_tournament(id)?;
+            let voter = find_voters(model, &voter.into_iter().collect::<Vec<_>>())?.remove(0);
+            model.amend_decision(&tournament, &voter, left, right, pick)?;
+            println!("Changed {}'s vote on #{} vs #{} to {}", voter.name, left, right, pick.name());
+            print_result(model, &model.get_tournament(id)?)
+        },
+        TournamentCommand::Abandon { id } => {
+            let tournament = model.get_tournament(id)?;
+            let tournament = model.abandon_tournament(&tournament)?;
+            println!("Abandoned tournament {}", tournament.name());
+            Ok(())
+        },
+    }
+}
+
+// start a new tournament, or carry on with one that was left unfinished
+fn choose_tournament(model: &dyn TournamentStore) -> Result<Tournament, Box<dyn Error>> {
+    let unfinished = model.get_unfinished_tournaments()?;
 
+    if !unfinished.is_empty() {
+        let mut options = vec![String::from("Start a new tournament")];
+        for t in &unfinished {
+            let anime = model.get_tournament_anime(t)?;
+            let remaining = model.tournament_remaining_anime(t)?;
+            options.push(format!("Resume tournament {} ({} of {} anime left)", t.name(), remaining.len(), anime.len()));
+        }
 
-    if !model.is_tournament_finished(&tournament) {
-        println!("Not yet!");
+        let choice = Select::new().items(&options).default(0).interact()?;
+        if choice > 0 {
+            return Ok(unfinished[choice - 1].clone());
+        }
     }
 
+    let slot = model.get_slots()?.into_iter().next().ok_or("There are no slots to run a tournament for")?;
+    let tournament = model.add_tournament(&slot, None, Format::default(), &find_voters(model, &[])?, &[])?;
+    let items = model.get_tournament_anime(&tournament)?;
+    println!("Tournament #{} has {} anime for {} slot", tournament.id, items.len(), slot.name);
+    Ok(tournament)
+}
+
+// Ask each pair of all the tournament's voters in turn, or only of `only` if
+// some are given, so the others can vote later.
+fn run_tournament(model: &dyn TournamentStore, tournament: &Tournament, only: &[Member]) -> Result<(), Box<dyn Error>> {
+    if tournament.mode.uses_ballots() {
+        return run_ballots(model, tournament, only);
+    }
+    let decisions = model.get_tournament_decisions(tournament)?;
+    if !decisions.is_empty() {
+        println!("Resuming tournament #{} after {} decisions", tournament.id, decisions.len());
+    }
+    let members = model.get_tournament_members(tournament)?;
+
+    'pairs: while let Some(decision) = model.next_tournament_decision(tournament)? {
+        let waiting = model.waiting_voters(tournament)?;
+        let voters: Vec<&Member> = waiting.iter()
+            .filter(|voter| only.is_empty() || only.iter().any(|m| m.id == voter.id))
+            .collect();
+        if voters.is_empty() {
+            let names: Vec<&str> = waiting.iter().map(|voter| voter.name.as_str()).collect();
+            println!("Waiting for {} to vote", names.join(", "));
+            return Ok(());
+        }
+
+        let left = model.get_anime_by_id(decision.left_anime)?;
+        let right = model.get_anime_by_id(decision.right_anime)?;
 
-    // run the tournament
-    while !model.is_tournament_finished(&tournament) {
-        let decision = model.next_tournament_decision(&tournament);
-        match decision {
-            Some(mut decision) => {
-                let left = model.get_anime_by_id(decision.left_anime).unwrap();
-                let right = model.get_anime_by_id(decision.right_anime).unwrap();
+        for voter in voters {
+            if members.len() > 1 {
+                println!("{}'s vote", voter.name);
+            }
+            let mut ballot = decision.clone();
+            ballot.pick = match ask_pick(&left.name, &right.name)? {
+                Some(pick) => Pick::to_opt(pick),
+                None => {
+                    undo_decision(model, tournament)?;
+                    continue 'pairs;
+                }
+            };
 
-                decision.pick = Pick::to_opt(ask_pick(left.name, right.name));
+            println!(" => {}", Pick::to_pick(ballot.pick).name());
 
-                println!(" => {}", Pick::to_pick(decision.pick).name());
-                
-                model.save_tournament_decision(&tournament, decision);
-            },
-            None => ()
+            model.save_tournament_decision(tournament, voter, ballot)?;
         }
     }
-    let winner = model.get_tournament_winner(&tournament).unwrap();
-    let winner_anime = model.get_anime_by_id(winner).unwrap();
-    println!("WINNER: {}", winner_anime.name);
-    
+
+    print_result(model, tournament)
 }
 
+// Ask each voter still to vote for their whole ballot, or only those in `only`
+fn run_ballots(model: &dyn TournamentStore, tournament: &Tournament, only: &[Member]) -> Result<(), Box<dyn Error>> {
+    let mut anime = model.get_tournament_anime(tournament)?;
+    anime.sort_by_key(|a| a.id);
+    let names: Vec<&str> = anime.iter().map(|a| a.name.as_str()).collect();
+
+    let waiting = model.waiting_voters(tournament)?;
+    let voters: Vec<&Member> = waiting.iter()
+        .filter(|voter| only.is_empty() || only.iter().any(|m| m.id == voter.id))
+        .collect();
+    if voters.is_empty() && !waiting.is_empty() {
+        let names: Vec<&str> = waiting.iter().map(|voter| voter.name.as_str()).collect();
+        println!("Waiting for {} to vote", names.join(", "));
+        return Ok(());
+    }
 
-fn ask_pick(left: String, right: String) -> Pick {
+    for voter in voters {
+        println!("{}'s ballot", voter.name);
+        if tournament.mode == Mode::Approval {
+            let ticked = MultiSelect::new()
+                .with_prompt("Tick every anime you'd watch (space to tick, enter when done)")
+                .items(&names)
+                .interact()?;
+            let approved: Vec<i32> = ticked.iter().map(|i| anime[*i].id).collect();
+            model.save_approval_ballot(tournament, voter, &approved)?;
+        } else {
+            let order = Sort::new()
+                .with_prompt("Put the anime in order, best first (space to pick one up or put it down, enter when done)")
+                .items(&names)
+                .interact()?;
+            let order: Vec<i32> = order.iter().map(|i| anime[*i].id).collect();
+            model.save_ranked_ballot(tournament, voter, &order)?;
+        }
+    }
+
+    print_result(model, tournament)
+}
+
+fn undo_decision(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    match model.undo_last_decision(tournament)? {
+        Some(ballot) => {
+            let voter = match ballot.voter {
+                ROOM_VOTER => String::from("the room"),
+                voter => model.get_member(voter)?.name
+            };
+            let left = model.get_anime_by_id(ballot.left_anime)?;
+            let right = model.get_anime_by_id(ballot.right_anime)?;
+            println!("Took back {}'s vote on {} vs {}", voter, left.name, right.name);
+        },
+        None => println!("There's nothing to take back in tournament {}", tournament.name()),
+    }
+    Ok(())
+}
+
+fn show_tournament(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    let anime = model.get_tournament_anime(tournament)?;
+    let remaining = model.tournament_remaining_anime(tournament)?;
+    println!("Tournament {}: {} slot, {}, {}", tournament.name(), slot_name(model, tournament.slot)?, tournament.mode_name(), tournament.status.name());
+    println!("Started {}", tournament.created_at.format("%Y-%m-%d %H:%M"));
+    if let Some(finished_at) = tournament.finished_at {
+        println!("Finished {}", finished_at.format("%Y-%m-%d %H:%M"));
+    }
+    let members = model.get_tournament_members(tournament)?;
+    let names: Vec<&str> = members.iter().map(|m| m.name.as_str()).collect();
+    println!("Voters: {}", names.join(", "));
+    if tournament.mode == Mode::Elimination {
+        println!("Pairing: {}", tournament.pairing.name());
+    }
+    println!("Random seed: {}", tournament.seed);
+    if tournament.mode.knocks_out() {
+        println!("{} of {} anime left", remaining.len(), anime.len());
+    }
+
+    for a in &anime {
+        let status = if remaining.contains(&a.id) { "" } else { "(out)" };
+        println!("  #{:<4} {} {}", a.id, a.name, status);
+    }
+
+    if tournament.mode.uses_ballots() {
+        show_ballots(model, tournament, &members)?;
+    }
+
+    let decisions = model.get_tournament_decisions(tournament)?;
+    if !decisions.is_empty() {
+        println!("Decisions:");
+    }
+    let ballots = model.get_tournament_ballots(tournament)?;
+    for decision in &decisions {
+        let left = model.get_anime_by_id(decision.left_anime)?;
+        let right = model.get_anime_by_id(decision.right_anime)?;
+        let by_room = ballots.iter().any(|ballot| ballot.voter == ROOM_VOTER && same_decision(ballot, decision));
+        println!("  {} vs {} => {}{}", left.name, right.name, Pick::to_pick(decision.pick).name(), if by_room { " (voted in the room)" } else { "" });
+
+        // how each member voted, when there's more than one
+        if members.len() > 1 {
+            for m in &members {
+                let ballot = ballots.iter().find(|ballot| ballot.voter == m.id
+                    && same_decision(ballot, decision));
+                let picked = match ballot.map(|ballot| (Pick::to_pick(ballot.pick), ballot)) {
+                    Some((Pick::Left, ballot)) => model.get_anime_by_id(ballot.left_anime)?.name,
+                    Some((Pick::Right, ballot)) => model.get_anime_by_id(ballot.right_anime)?.name,
+                    Some((Pick::Undecided, _)) => String::from("draw"),
+                    None => continue
+                };
+                println!("      {}: {}", m.name, picked);
+            }
+        }
+    }
+
+    print_result(model, tournament)
+}
+
+// each voter's whole ballot: the anime they ticked, or their order
+fn show_ballots(model: &dyn TournamentStore, tournament: &Tournament, members: &[Member]) -> Result<(), Box<dyn Error>> {
+    let approval = model.get_approval_ballots(tournament)?;
+    let ranked = model.get_ranked_ballots(tournament)?;
+    if approval.is_empty() && ranked.is_empty() {
+        return Ok(());
+    }
+
+    println!("Ballots:");
+    for m in members {
+        if !approval.iter().any(|ballot| ballot.voter == m.id) && !ranked.iter().any(|ballot| ballot.voter == m.id) {
+            continue;
+        }
+        let (anime_ids, separator): (Vec<i32>, &str) = if tournament.mode == Mode::Approval {
+            (approval.iter().filter(|ballot| ballot.voter == m.id && ballot.approved).map(|ballot| ballot.anime).collect(), ", ")
+        } else {
+            (ranked.iter().filter(|ballot| ballot.voter == m.id).map(|ballot| ballot.anime).collect(), " > ")
+        };
+
+        let mut names = vec![];
+        for anime_id in anime_ids {
+            names.push(model.get_anime_by_id(anime_id)?.name);
+        }
+        println!("  {}: {}", m.name, if names.is_empty() { String::from("none") } else { names.join(separator) });
+    }
+    Ok(())
+}
+
+fn show_bracket(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    if !tournament.is_bracket() {
+        return Err(format!("Tournament {} isn't a bracket", tournament.name()).into());
+    }
+    let matches = model.get_bracket_matches(tournament)?;
+    let decisions = model.get_tournament_decisions(tournament)?;
+    let played = bracket::play(&matches, &decisions);
+    let next = model.next_tournament_decision(tournament)?;
+
+    // an anime's name once it's known, or where it will come from
+    let side = |anime: Option<i32>, source: Source| -> Result<String, Box<dyn Error>> {
+        Ok(match (anime, source) {
+            (Some(anime_id), _) => model.get_anime_by_id(anime_id)?.name,
+            (None, Source::Winner(number)) => format!("winner of #{}", number),
+            (None, Source::Loser(number)) => format!("loser of #{}", number),
+            (None, Source::Anime(anime_id)) => format!("anime #{}", anime_id),
+        })
+    };
+
+    let mut heading = None;
+    for (m, (left, right, winner)) in matches.iter().zip(played.iter()) {
+        if heading != Some((m.bracket, m.round)) {
+            match m.bracket {
+                Bracket::Winners => println!("Winners' bracket, round {}", m.round),
+                Bracket::Losers => println!("Losers' bracket, round {}", m.round),
+                Bracket::Final => println!("Final"),
+            }
+            heading = Some((m.bracket, m.round));
+        }
+
+        let result = match (winner, &next) {
+            (Some(winner), _) => format!(" => {}", model.get_anime_by_id(*winner)?.name),
+            (None, Some(next)) if (Some(next.left_anime), Some(next.right_anime)) == (*left, *right)
+                || (Some(next.right_anime), Some(next.left_anime)) == (*left, *right) => String::from("  (next)"),
+            (None, _) => String::new()
+        };
+        println!("  #{:<3} {} vs {}{}", m.number, side(*left, m.left())?, side(*right, m.right())?, result);
+    }
+    Ok(())
+}
+
+// whatever the tournament's mode is for
+fn print_result(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    match tournament.mode {
+        Mode::Elimination | Mode::SingleElimination | Mode::DoubleElimination => print_winner(model, tournament),
+        Mode::Ranking => print_ranking(model, tournament),
+        Mode::TopK => print_top_k(model, tournament),
+        Mode::Swiss | Mode::RoundRobin => print_standings(model, tournament),
+        Mode::Approval | Mode::Borda | Mode::InstantRunoff => print_tally(model, tournament),
+    }
+}
+
+fn print_winner(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    match model.get_tournament_winner(tournament)? {
+        Some(winner) => {
+            let winner_anime = model.get_anime_by_id(winner)?;
+            println!("WINNER: {}", winner_anime.name);
+        },
+        None => print_unfinished(model, tournament)?
+    }
+    Ok(())
+}
+
+fn print_ranking(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    let ranking = model.get_tournament_ranking(tournament)?;
+    if ranking.is_empty() {
+        return print_unfinished(model, tournament);
+    }
+
+    if !model.is_tournament_finished(tournament)? {
+        println!("Ranking so far:");
+    }
+    for (place, anime) in ranking.iter().enumerate() {
+        println!("{:>3}. {}", place + 1, anime.name);
+    }
+    Ok(())
+}
+
+fn print_top_k(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    let top = model.get_tournament_top_k(tournament)?;
+    if top.is_empty() {
+        return print_unfinished(model, tournament);
+    }
+
+    if !model.is_tournament_finished(tournament)? {
+        println!("Top picks so far:");
+    }
+    for (place, anime) in top.iter().enumerate() {
+        println!("{:>3}. {}", place + 1, anime.name);
+    }
+    Ok(())
+}
+
+fn print_standings(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    if tournament.mode != Mode::Swiss && tournament.mode != Mode::RoundRobin {
+        return Err(format!("Tournament {} is a {} tournament, which has no standings", tournament.name(), tournament.mode.name()).into());
+    }
+    let standings = model.get_tournament_standings(tournament)?;
+    if standings.is_empty() {
+        return print_unfinished(model, tournament);
+    }
+
+    if !model.is_tournament_finished(tournament)? {
+        println!("Standings so far:");
+    }
+    print!("     {:<40} {:>5}  {:<8}", "", "Score", "W-D-L");
+    for tiebreak in engine::tiebreaks(tournament) {
+        print!(" {:>8}", tiebreak);
+    }
+    println!();
+    for (place, standing) in standings.iter().enumerate() {
+        let anime = model.get_anime_by_id(standing.anime)?;
+        let record = format!("{}-{}-{}", standing.wins, standing.draws, standing.losses);
+        print!("{:>3}. {:<40} {:>5.1}  {:<8}", place + 1, anime.name, standing.score, record);
+        for tiebreak in &standing.tiebreaks {
+            print!(" {:>8.2}", tiebreak);
+        }
+        println!("{}", if standing.byes > 0 { "  (bye)" } else { "" });
+    }
+
+    if tournament.mode == Mode::RoundRobin {
+        let order: Vec<i32> = standings.iter().map(|standing| standing.anime).collect();
+        print_head_to_head(model, tournament, &order)?;
+    }
+    Ok(())
+}
+
+// the whole ballots counted, with the winner once everyone has voted
+fn print_tally(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    let tally = model.get_tournament_tally(tournament)?;
+    let finished = model.is_tournament_finished(tournament)?;
+
+    if !finished {
+        println!("Count so far:");
+    }
+    let points = match tournament.mode {
+        Mode::Approval => "Approvals",
+        Mode::Borda => "Points",
+        // in the last round each anime was still in
+        _ => "Votes"
+    };
+    println!("     {:<40} {:>9}", "", points);
+    for (place, (anime_id, points)) in tally.standings.iter().enumerate() {
+        let anime = model.get_anime_by_id(*anime_id)?;
+        println!("{:>3}. {:<40} {:>9}", place + 1, anime.name, points);
+    }
+
+    match (tally.winner, finished) {
+        (Some(winner), true) => println!("WINNER: {}", model.get_anime_by_id(winner)?.name),
+        (None, true) => println!("No winner: a tie for first place"),
+        (_, false) => print_unfinished(model, tournament)?
+    }
+    Ok(())
+}
+
+// each row's result against each column, in the given order
+fn print_head_to_head(model: &dyn TournamentStore, tournament: &Tournament, order: &[i32]) -> Result<(), Box<dyn Error>> {
+    let head_to_head = model.get_head_to_head(tournament)?;
+
+    println!("Head to head, for the row against the column:");
+    print!("{:<30}", "");
+    for anime_id in order {
+        print!(" {:>4}", format!("#{}", anime_id));
+    }
+    println!();
+    for a in order {
+        let anime = model.get_anime_by_id(*a)?;
+        print!("{:<30}", format!("#{} {}", a, anime.name).chars().take(29).collect::<String>());
+        for b in order {
+            let result = match head_to_head.result(*a, *b) {
+                _ if a == b => "-",
+                Some(Ordering::Greater) => "W",
+                Some(Ordering::Equal) => "D",
+                Some(Ordering::Less) => "L",
+                None => ""
+            };
+            print!(" {:>4}", result);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn print_strength(model: &dyn TournamentStore, tournaments: &[Tournament]) -> Result<(), Box<dyn Error>> {
+    let fit = model.fit_bradley_terry(tournaments)?;
+    let ranking = fit.ranking();
+
+    println!("Strength, with a 95% confidence interval (0 is average):");
+    for (place, anime_id) in ranking.iter().enumerate() {
+        let anime = model.get_anime_by_id(*anime_id)?;
+        let strength = fit.strength(*anime_id).unwrap_or(0.0);
+        let (low, high) = fit.interval(*anime_id).unwrap_or((strength, strength));
+        println!("{:>3}. {:<40} {:>6.2}  ({:.2} to {:.2})", place + 1, anime.name, strength, low, high);
+    }
+
+    // how sure we can be of each place
+    if ranking.len() > 1 {
+        println!();
+    }
+    for pair in ranking.windows(2) {
+        let better = model.get_anime_by_id(pair[0])?;
+        let worse = model.get_anime_by_id(pair[1])?;
+        let probability = fit.probability(pair[0], pair[1]).unwrap_or(0.5);
+        println!("{} is preferred over {} with {:.0}% probability", better.name, worse.name, probability * 100.0);
+    }
+    Ok(())
+}
+
+fn print_results(model: &dyn TournamentStore, tournament: &Tournament, method: Method) -> Result<(), Box<dyn Error>> {
+    let matrix = model.get_tournament_matrix(tournament)?;
+    let anime = model.get_tournament_anime(tournament)?;
+    let name = |anime_id: i32| anime.iter().find(|a| a.id == anime_id).map(|a| a.name.as_str()).unwrap_or("?");
+
+    println!("Ranking by {}:", method.name());
+    for (place, anime_id) in results::rank(method, &matrix).iter().enumerate() {
+        println!("{:>3}. {}", place + 1, name(*anime_id));
+    }
+
+    match results::condorcet_winner(&matrix) {
+        Some(winner) => println!("Condorcet winner: {}, who beats every other anime head to head", name(winner)),
+        None => println!("No Condorcet winner: no anime beats every other one head to head"),
+    }
+    let unvoted = matrix.anime.iter().enumerate()
+        .flat_map(|(i, a)| matrix.anime[i + 1..].iter().map(move |b| (*a, *b)))
+        .filter(|(a, b)| matrix.votes(*a, *b) + matrix.votes(*b, *a) == 0)
+        .count();
+    if unvoted > 0 {
+        println!("Pairs not voted on: {}, as the tournament didn't need them", unvoted);
+    }
+
+    // the votes themselves, so the result can be checked
+    println!("Votes for the row over the column:");
+    print!("{:<30}", "");
+    for anime_id in &matrix.anime {
+        print!(" {:>4}", format!("#{}", anime_id));
+    }
+    println!();
+    for a in &matrix.anime {
+        print!("{:<30}", format!("#{} {}", a, name(*a)).chars().take(29).collect::<String>());
+        for b in &matrix.anime {
+            if a == b {
+                print!(" {:>4}", "-");
+            } else {
+                print!(" {:>4}", matrix.votes(*a, *b));
+            }
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn print_unfinished(model: &dyn TournamentStore, tournament: &Tournament) -> Result<(), Box<dyn Error>> {
+    let anime = model.get_tournament_anime(tournament)?;
+    let remaining = model.tournament_remaining_anime(tournament)?;
+
+    if anime.is_empty() {
+        println!("No winner: tournament #{} has no anime", tournament.id);
+    } else if tournament.mode.knocks_out() {
+        println!("Tournament #{} isn't finished: {} anime left", tournament.id, remaining.len());
+    } else {
+        println!("Tournament #{} isn't finished yet", tournament.id);
+    }
+    Ok(())
+}
+
+fn print_anime(model: &dyn TournamentStore, anime: &Anime) -> Result<(), Box<dyn Error>> {
+    let slots: Vec<String> = model.get_anime_slots(anime.id)?.into_iter()
+        .map(|slot| slot.name)
+        .collect();
+    let episodes = anime.episodes.map(|episodes| format!("{} episodes", episodes)).unwrap_or_default();
+
+    println!("#{:<4} {:<40} {:<12} {}", anime.id, anime.name, episodes, slots.join(", "));
+    Ok(())
+}
+
+fn print_slot(slot: &Slot) {
+    let duration = slot.duration.map(|duration| format!("{} minutes", duration)).unwrap_or_default();
+    let max_episodes = slot.max_episodes.map(|episodes| format!("up to {} episodes", episodes)).unwrap_or_default();
+
+    println!("{:<3} {:<20} {:<12} {}", slot.position, slot.name, duration, max_episodes);
+}
+
+// tournaments keep their slot's id even if the slot is later removed
+fn slot_name(model: &dyn TournamentStore, slot_id: i32) -> Result<String, Box<dyn Error>> {
+    match model.get_slot(slot_id) {
+        Ok(slot) => Ok(slot.name),
+        Err(TournamentError::NotFound { .. }) => Ok(format!("slot #{}", slot_id)),
+        Err(err) => Err(err.into())
+    }
+}
+
+// the pick, or None to undo the last one
+fn ask_pick(left: &str, right: &str) -> Result<Option<Pick>, Box<dyn Error>> {
     println!("Decision: {} vs {}", left, right);
 
-    let input = Input::<String>::new().with_prompt("[L/R] ").interact().unwrap();
-    match input.as_str() {
-        "l"|"L" => Pick::Left,
-        "r"|"R" => Pick::Right,
-        _ => ask_pick(left, right)
+    loop {
+        let input = Input::<String>::new().with_prompt("[L/R/D/U] ").interact()?;
+        match input.as_str() {
+            "l"|"L" => return Ok(Some(Pick::Left)),
+            "r"|"R" => return Ok(Some(Pick::Right)),
+            // can't choose between them
+            "d"|"D" => return Ok(Some(Pick::Undecided)),
+            // take back the last answer
+            "u"|"U" => return Ok(None),
+            _ => ()
+        }
     }
 }
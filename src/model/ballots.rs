@@ -0,0 +1,177 @@
+// Whole ballots, for when asking pair by pair would take too long: each voter
+// either ticks every anime they'd watch, or puts all of them in order.
+//
+// Each kind of count gives the anime's points, best first, and the winner. A
+// tie for first place has no winner.
+
+use std::collections::HashMap;
+
+use super::models::*;
+
+pub struct Tally {
+    // best first, with each anime's points: its approvals, its Borda points,
+    // or for instant runoff its votes in the last round it was in
+    pub standings: Vec<(i32, f64)>,
+    pub winner: Option<i32>,
+}
+
+impl Tally {
+    // sorted by points, keeping the order of `anime` for those level on points
+    fn by_points(mut standings: Vec<(i32, f64)>) -> Tally {
+        standings.sort_by(|(_, x), (_, y)| y.partial_cmp(x).unwrap_or(std::cmp::Ordering::Equal));
+        let winner = match standings.as_slice() {
+            [(_, first), (_, second), ..] if first == second => None,
+            [(anime, points), ..] if *points > 0.0 => Some(*anime),
+            _ => None
+        };
+        Tally { standings, winner }
+    }
+}
+
+// Approval: a point for every ballot an anime is ticked on
+pub fn approval(anime: &[i32], ballots: &[ApprovalBallot]) -> Tally {
+    let approvals = |a: &i32| ballots.iter().filter(|ballot| ballot.anime == *a && ballot.approved).count() as f64;
+    Tally::by_points(anime.iter().map(|a| (*a, approvals(a))).collect())
+}
+
+// each voter's order, best first
+fn orders(ballots: &[RankedBallot]) -> Vec<Vec<i32>> {
+    let mut by_voter: HashMap<i32, Vec<&RankedBallot>> = HashMap::new();
+    for ballot in ballots {
+        by_voter.entry(ballot.voter).or_default().push(ballot);
+    }
+
+    let mut voters: Vec<i32> = by_voter.keys().copied().collect();
+    voters.sort_unstable();
+    voters.iter().map(|voter| {
+        let mut ranked = by_voter[voter].clone();
+        ranked.sort_by_key(|ballot| ballot.position);
+        ranked.iter().map(|ballot| ballot.anime).collect()
+    }).collect()
+}
+
+fn borda_points(anime: &[i32], ballots: &[RankedBallot]) -> Vec<(i32, f64)> {
+    let orders = orders(ballots);
+    anime.iter().map(|a| {
+        let points = orders.iter()
+            .filter_map(|order| order.iter().position(|b| b == a).map(|place| (order.len() - 1 - place) as f64))
+            .sum();
+        (*a, points)
+    }).collect()
+}
+
+// Borda: on each ballot, a point for every anime placed below
+pub fn borda(anime: &[i32], ballots: &[RankedBallot]) -> Tally {
+    Tally::by_points(borda_points(anime, ballots))
+}
+
+// Instant runoff: each ballot counts for its first choice still running, and
+// the anime with the fewest votes goes out, round by round, until one has a
+// majority. A tie for fewest votes goes out by fewer Borda points, then by the
+// order of `anime`.
+pub fn instant_runoff(anime: &[i32], ballots: &[RankedBallot]) -> Tally {
+    let orders = orders(ballots);
+    if orders.is_empty() {
+        return Tally::by_points(anime.iter().map(|a| (*a, 0.0)).collect());
+    }
+    let borda = borda_points(anime, ballots);
+    let borda_of = |a: i32| borda.iter().find(|(b, _)| *b == a).map(|(_, points)| *points).unwrap_or(0.0);
+
+    let mut running = anime.to_vec();
+    // in the order they went out
+    let mut out: Vec<(i32, f64)> = vec![];
+    loop {
+        let votes: Vec<(i32, f64)> = running.iter().map(|a| {
+            let votes = orders.iter()
+                .filter(|order| order.iter().find(|b| running.contains(b)) == Some(a))
+                .count();
+            (*a, votes as f64)
+        }).collect();
+        let total: f64 = votes.iter().map(|(_, votes)| votes).sum();
+
+        let majority = votes.iter().any(|(_, votes)| *votes > total / 2.0);
+        if majority || running.len() < 2 {
+            let mut tally = Tally::by_points(votes);
+            tally.standings.extend(out.into_iter().rev());
+            return tally;
+        }
+
+        let loser = votes.iter()
+            .rev()
+            .min_by(|(a, x), (b, y)| x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| borda_of(*a).partial_cmp(&borda_of(*b)).unwrap_or(std::cmp::Ordering::Equal)))
+            .copied();
+        if let Some((loser, votes)) = loser {
+            running.retain(|a| *a != loser);
+            out.push((loser, votes));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approved(voter: i32, anime: i32, approved: bool) -> ApprovalBallot {
+        ApprovalBallot { tournament: 1, voter, anime, approved }
+    }
+
+    // a voter's order, best first
+    fn ranked(voter: i32, order: &[i32]) -> Vec<RankedBallot> {
+        order.iter().enumerate()
+            .map(|(i, anime)| RankedBallot { tournament: 1, voter, anime: *anime, position: i as i32 + 1 })
+            .collect()
+    }
+
+    #[test]
+    fn the_most_approved_wins() {
+        let ballots = [
+            approved(1, 1, true), approved(1, 2, true), approved(1, 3, false),
+            approved(2, 1, false), approved(2, 2, true), approved(2, 3, true),
+        ];
+        let tally = approval(&[1, 2, 3], &ballots);
+        assert_eq!(tally.standings, vec![(2, 2.0), (1, 1.0), (3, 1.0)]);
+        assert_eq!(tally.winner, Some(2));
+    }
+
+    #[test]
+    fn a_tie_for_first_has_no_winner() {
+        let ballots = [approved(1, 1, true), approved(2, 2, true)];
+        assert_eq!(approval(&[1, 2], &ballots).winner, None);
+        assert_eq!(approval(&[1, 2], &[]).winner, None);
+    }
+
+    #[test]
+    fn borda_gives_a_point_for_each_anime_placed_below() {
+        let ballots: Vec<RankedBallot> = [ranked(1, &[1, 2, 3]), ranked(2, &[2, 3, 1]), ranked(3, &[2, 1, 3])].concat();
+        let tally = borda(&[1, 2, 3], &ballots);
+        assert_eq!(tally.standings, vec![(2, 5.0), (1, 3.0), (3, 1.0)]);
+        assert_eq!(tally.winner, Some(2));
+    }
+
+    #[test]
+    fn instant_runoff_passes_the_votes_of_those_out_on() {
+        // 2 and 3 have the fewest first choices, and 3 goes out on fewer Borda
+        // points, so its voters' next choice 2 has a majority though 1 had the
+        // most first choices
+        let ballots: Vec<RankedBallot> = [
+            ranked(1, &[1, 2, 3]),
+            ranked(2, &[1, 3, 2]),
+            ranked(3, &[2, 1, 3]),
+            ranked(4, &[3, 2, 1]),
+            ranked(5, &[3, 2, 1]),
+            ranked(6, &[2, 3, 1]),
+            ranked(7, &[1, 2, 3]),
+        ].concat();
+        let tally = instant_runoff(&[1, 2, 3], &ballots);
+        assert_eq!(tally.winner, Some(2));
+        assert_eq!(tally.standings, vec![(2, 4.0), (1, 3.0), (3, 2.0)]);
+    }
+
+    #[test]
+    fn instant_runoff_without_ballots_has_no_winner() {
+        let tally = instant_runoff(&[1, 2], &[]);
+        assert_eq!(tally.winner, None);
+        assert_eq!(tally.standings, vec![(1, 0.0), (2, 0.0)]);
+    }
+}
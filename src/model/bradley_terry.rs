@@ -0,0 +1,234 @@
+// Bradley-Terry: fit a strength to each anime so that anime `a` is picked over
+// `b` with probability exp(a) / (exp(a) + exp(b)), as well as the ballots allow.
+//
+// Every ballot counts, a draw as half a win to each. Each anime also gets a
+// half win and a half loss against an average anime, so one that never won
+// (or never lost) still gets a finite strength. The strengths are then shifted
+// so the average of them is 0.
+
+use super::models::*;
+
+const ITERATIONS: usize = 1000;
+const TOLERANCE: f64 = 0.000_001;
+// the prior games against the average anime
+const PRIOR: f64 = 0.5;
+
+pub struct Fit {
+    pub anime: Vec<i32>,
+    // log strengths, 0 for an average one of these anime
+    pub strength: Vec<f64>,
+    pub std_error: Vec<f64>,
+}
+
+impl Fit {
+    fn index(&self, anime_id: i32) -> Option<usize> {
+        self.anime.iter().position(|a| *a == anime_id)
+    }
+
+    pub fn strength(&self, anime_id: i32) -> Option<f64> {
+        self.index(anime_id).map(|i| self.strength[i])
+    }
+
+    // the range the strength is in with about 95% confidence
+    pub fn interval(&self, anime_id: i32) -> Option<(f64, f64)> {
+        self.index(anime_id).map(|i| (self.strength[i] - 1.96 * self.std_error[i], self.strength[i] + 1.96 * self.std_error[i]))
+    }
+
+    // how likely `a` is to be picked over `b`
+    pub fn probability(&self, a: i32, b: i32) -> Option<f64> {
+        Some(1.0 / (1.0 + (self.strength(b)? - self.strength(a)?).exp()))
+    }
+
+    // best first
+    pub fn ranking(&self) -> Vec<i32> {
+        let mut order: Vec<usize> = (0..self.anime.len()).collect();
+        order.sort_by(|i, j| self.strength[*j].partial_cmp(&self.strength[*i]).unwrap_or(std::cmp::Ordering::Equal));
+        order.into_iter().map(|i| self.anime[i]).collect()
+    }
+}
+
+pub fn fit(anime: &[i32], ballots: &[Ballot]) -> Fit {
+    let picks: Vec<(i32, i32, Option<bool>)> = ballots.iter()
+        .map(|ballot| (ballot.left_anime, ballot.right_anime, ballot.pick))
+        .collect();
+    fit_picks(anime, &picks)
+}
+
+// the same from the decisions of one tournament, with each pair counted once
+pub fn fit_decisions(anime: &[i32], decisions: &[Decision]) -> Fit {
+    let picks: Vec<(i32, i32, Option<bool>)> = decisions.iter()
+        .map(|decision| (decision.left_anime, decision.right_anime, decision.pick))
+        .collect();
+    fit_picks(anime, &picks)
+}
+
+// each pick is (left anime, right anime, pick)
+fn fit_picks(anime: &[i32], picks: &[(i32, i32, Option<bool>)]) -> Fit {
+    let n = anime.len();
+    let index = |anime_id: i32| anime.iter().position(|a| *a == anime_id);
+
+    // wins[i][j]: how many times anime[i] was picked over anime[j]
+    let mut wins = vec![vec![0.0; n]; n];
+    for (left, right, pick) in picks {
+        let (i, j) = match (index(*left), index(*right)) {
+            (Some(i), Some(j)) => (i, j),
+            _ => continue
+        };
+        match Pick::to_pick(*pick) {
+            Pick::Left => wins[i][j] += 1.0,
+            Pick::Right => wins[j][i] += 1.0,
+            Pick::Undecided => {
+                wins[i][j] += 0.5;
+                wins[j][i] += 0.5;
+            }
+        }
+    }
+
+    // Hunter's minorization-maximization, where the average anime has strength 1
+    let mut p = vec![1.0; n];
+    for _ in 0..ITERATIONS {
+        let mut change: f64 = 0.0;
+        for i in 0..n {
+            let won: f64 = wins[i].iter().sum::<f64>() + PRIOR;
+            let mut games = 2.0 * PRIOR / (p[i] + 1.0);
+            for j in 0..n {
+                let played = wins[i][j] + wins[j][i];
+                if j != i && played > 0.0 {
+                    games += played / (p[i] + p[j]);
+                }
+            }
+            let updated = won / games;
+            change = change.max((updated / p[i]).ln().abs());
+            p[i] = updated;
+        }
+        if change < TOLERANCE {
+            break;
+        }
+    }
+
+    // the standard errors come from the inverse of the Fisher information
+    let mut information = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        let q = p[i] / (p[i] + 1.0);
+        information[i][i] += 2.0 * PRIOR * q * (1.0 - q);
+        for j in 0..n {
+            let played = wins[i][j] + wins[j][i];
+            if j != i && played > 0.0 {
+                let q = p[i] / (p[i] + p[j]);
+                information[i][i] += played * q * (1.0 - q);
+                information[i][j] -= played * q * (1.0 - q);
+            }
+        }
+    }
+    let covariance = invert(information);
+
+    // Only the differences between strengths mean anything, so measure them from
+    // their mean, and their errors too: the variance of s[i] - mean(s).
+    let strength: Vec<f64> = p.iter().map(|p| p.ln()).collect();
+    let mean = strength.iter().sum::<f64>() / n.max(1) as f64;
+    let total: f64 = covariance.iter().flatten().sum();
+    let std_error = (0..n).map(|i| {
+        let row: f64 = covariance[i].iter().sum();
+        let variance = covariance[i][i] - 2.0 * row / n as f64 + total / (n * n) as f64;
+        variance.max(0.0).sqrt()
+    }).collect();
+
+    Fit {
+        anime: anime.to_vec(),
+        strength: strength.iter().map(|s| s - mean).collect(),
+        std_error,
+    }
+}
+
+// Gauss-Jordan elimination; the information matrix is always invertible thanks
+// to the prior games
+fn invert(mut matrix: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|a, b| matrix[*a][col].abs().partial_cmp(&matrix[*b][col].abs()).unwrap_or(std::cmp::Ordering::Equal)).unwrap_or(col);
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let scale = matrix[col][col];
+        if scale == 0.0 {
+            continue;
+        }
+        for k in 0..n {
+            matrix[col][k] /= scale;
+            inverse[col][k] /= scale;
+        }
+        for row in 0..n {
+            if row != col {
+                let factor = matrix[row][col];
+                for k in 0..n {
+                    matrix[row][k] -= factor * matrix[col][k];
+                    inverse[row][k] -= factor * inverse[col][k];
+                }
+            }
+        }
+    }
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ballot(left_anime: i32, right_anime: i32, pick: Pick) -> Ballot {
+        Ballot { tournament: 1, left_anime, right_anime, pick: Pick::to_opt(pick), voter: 1, number: 0, meeting: 0 }
+    }
+
+    #[test]
+    fn no_ballots_leaves_every_anime_average() {
+        let fit = fit(&[1, 2], &[]);
+        assert_eq!(fit.strength(1), Some(0.0));
+        assert_eq!(fit.probability(1, 2), Some(0.5));
+        assert_eq!(fit.strength(3), None);
+    }
+
+    #[test]
+    fn the_winners_come_out_stronger() {
+        let ballots = vec![
+            ballot(1, 2, Pick::Left),
+            ballot(1, 2, Pick::Left),
+            ballot(2, 3, Pick::Left),
+            ballot(3, 2, Pick::Right),
+            ballot(1, 3, Pick::Left),
+        ];
+        let fit = fit(&[1, 2, 3], &ballots);
+        assert_eq!(fit.ranking(), vec![1, 2, 3]);
+
+        let total: f64 = fit.strength.iter().sum();
+        assert!(total.abs() < 1e-9);
+        let p = fit.probability(1, 3).unwrap();
+        assert!(p > 0.5 && (p + fit.probability(3, 1).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn an_anime_that_never_lost_still_has_a_finite_strength() {
+        let fit = fit(&[1, 2], &[ballot(1, 2, Pick::Left), ballot(2, 1, Pick::Right)]);
+        let (low, high) = fit.interval(1).unwrap();
+        assert!(fit.strength(1).unwrap().is_finite());
+        assert!(low < fit.strength(1).unwrap() && fit.strength(1).unwrap() < high);
+    }
+
+    #[test]
+    fn a_draw_counts_half_to_each() {
+        let fit = fit(&[1, 2], &[ballot(1, 2, Pick::Undecided)]);
+        assert!((fit.strength(1).unwrap() - fit.strength(2).unwrap()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn decisions_count_each_pair_once() {
+        let decisions = vec![Decision { tournament: 1, left_anime: 1, right_anime: 2, pick: Some(false), meeting: 0 }];
+        assert_eq!(fit_decisions(&[1, 2], &decisions).ranking(), vec![2, 1]);
+    }
+
+    #[test]
+    fn inverts_a_matrix() {
+        let inverse = invert(vec![vec![2.0, 1.0], vec![1.0, 1.0]]);
+        assert_eq!(inverse, vec![vec![1.0, -1.0], vec![-1.0, 2.0]]);
+    }
+}
@@ -0,0 +1,638 @@
+use diesel::prelude::*;
+use diesel::connection::TransactionManager;
+#[cfg(feature = "mysql")]
+use diesel::mysql::MysqlConnection;
+#[cfg(feature = "sqlite")]
+use diesel::sqlite::SqliteConnection;
+use diesel::sql_types::{BigInt};
+
+use super::schema;
+use super::models::*;
+use super::{TournamentStore, TournamentError, Result};
+
+#[cfg(not(any(feature = "mysql", feature = "sqlite")))]
+compile_error!("Enable at least one database feature: \"mysql\" or \"sqlite\"");
+
+pub enum DbConnection {
+    #[cfg(feature = "mysql")]
+    Mysql(MysqlConnection),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteConnection),
+}
+
+// Run the same diesel code against whichever connection we have.
+// The body is compiled once per backend, so it can use any query both support.
+macro_rules! with_connection {
+    ($model:expr, |$conn:ident| $body:expr) => {
+        match &$model.connection {
+            #[cfg(feature = "mysql")]
+            DbConnection::Mysql($conn) => $body,
+            #[cfg(feature = "sqlite")]
+            DbConnection::Sqlite($conn) => $body,
+        }
+    };
+}
+
+// the scheme of a URL such as "postgres://...", but not a Windows drive letter
+fn url_scheme(database_url: &str) -> Option<&str> {
+    let (scheme, _) = database_url.split_once(':')?;
+    let is_scheme = scheme.len() > 1 && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c));
+    if is_scheme { Some(scheme) } else { None }
+}
+
+// Pick the backend from the scheme of the URL:
+// "mysql://..." for MySQL, "sqlite://path" or a plain file path for SQLite.
+// Anything else is refused, rather than opened as a new SQLite file.
+fn establish_connection(database_url: &str) -> Result<DbConnection> {
+    if database_url.starts_with("mysql://") {
+        #[cfg(feature = "mysql")]
+        return Ok(DbConnection::Mysql(MysqlConnection::establish(database_url)?));
+
+        #[cfg(not(feature = "mysql"))]
+        return Err(TournamentError::Connection(format!("{} needs the \"mysql\" feature", database_url)));
+    }
+
+    let path = match (database_url.strip_prefix("sqlite://"), url_scheme(database_url)) {
+        (Some(path), _) => path,
+        (None, None) => database_url,
+        (None, Some(scheme)) => return Err(TournamentError::Connection(format!("{}: URLs aren't supported, only mysql://, sqlite:// or a file path", scheme))),
+    };
+
+    #[cfg(feature = "sqlite")]
+    return Ok(DbConnection::Sqlite(SqliteConnection::establish(path)?));
+
+    #[cfg(not(feature = "sqlite"))]
+    return Err(TournamentError::Connection(format!("{} needs the \"sqlite\" feature", path)));
+}
+
+pub struct Model {
+    pub connection: DbConnection
+}
+
+#[derive(Debug,QueryableByName)]
+struct InsertId {
+    #[sql_type="BigInt"]
+    pub id: i64
+}
+
+impl Model {
+    pub fn connect(database_url: &str) -> Result<Model> {
+        let connection = establish_connection(database_url)?;
+
+        Ok(Model {
+            connection
+        })
+    }
+
+    fn get_last_inserted_id(&self) -> Result<i32> {
+        let results: Vec<InsertId> = match &self.connection {
+            #[cfg(feature = "mysql")]
+            DbConnection::Mysql(conn) => diesel::sql_query("SELECT LAST_INSERT_ID() 'id';").load(conn)?,
+            #[cfg(feature = "sqlite")]
+            DbConnection::Sqlite(conn) => diesel::sql_query("SELECT last_insert_rowid() AS id;").load(conn)?,
+        };
+        let insertid: i64 = results.first().map(|result| result.id).unwrap_or(0);
+        Ok(insertid as i32)
+    }
+}
+
+impl TournamentStore for Model {
+    fn begin_transaction(&self) -> Result<()> {
+        Ok(with_connection!(self, |conn| conn.transaction_manager().begin_transaction(conn))?)
+    }
+
+    fn commit_transaction(&self) -> Result<()> {
+        Ok(with_connection!(self, |conn| conn.transaction_manager().commit_transaction(conn))?)
+    }
+
+    fn rollback_transaction(&self) -> Result<()> {
+        Ok(with_connection!(self, |conn| conn.transaction_manager().rollback_transaction(conn))?)
+    }
+
+    fn add_anime(&self, name: &str, episodes: Option<i32>) -> Result<Anime> {
+        use schema::anime;
+
+        let mut new_anime = Anime {
+            id: 0,
+            name: name.to_string(),
+            episodes,
+        };
+
+        with_connection!(self, |conn| diesel::insert_into(anime::table)
+            .values((
+                anime::name.eq(&new_anime.name),
+                anime::episodes.eq(new_anime.episodes),
+            ))
+            .execute(conn))?;
+
+        new_anime.id = self.get_last_inserted_id()?;
+        Ok(new_anime)
+    }
+
+    fn get_anime(&self) -> Result<Vec<Anime>> {
+        use schema::anime::dsl::*;
+
+        Ok(with_connection!(self, |conn| anime.order(name)
+            .load::<Anime>(conn))?)
+    }
+
+    fn get_anime_by_id(&self, anime_id: i32) -> Result<Anime> {
+        use schema::anime::dsl::*;
+
+        with_connection!(self, |conn| anime.filter(id.eq(anime_id))
+            .first::<Anime>(conn)
+            .optional())?
+            .ok_or_else(|| TournamentError::not_found("anime", anime_id))
+    }
+
+    fn get_anime_for_slot(&self, slot: &Slot) -> Result<Vec<Anime>> {
+        use schema::{anime, anime_slots};
+
+        let anime_ids: Vec<i32> = with_connection!(self, |conn| anime_slots::table
+            .filter(anime_slots::slot.eq(slot.id))
+            .select(anime_slots::anime)
+            .load::<i32>(conn))?;
+
+        Ok(with_connection!(self, |conn| anime::table.filter(anime::id.eq_any(&anime_ids))
+            .order(anime::name)
+            .load::<Anime>(conn))?)
+    }
+
+    fn update_anime(&self, a: &Anime) -> Result<()> {
+        use schema::anime::dsl::*;
+
+        let updated = with_connection!(self, |conn| diesel::update(anime.filter(id.eq(a.id)))
+            .set((
+                name.eq(&a.name),
+                episodes.eq(a.episodes),
+            ))
+            .execute(conn))?;
+
+        if updated == 0 {
+            return Err(TournamentError::not_found("anime", a.id));
+        }
+        Ok(())
+    }
+
+    fn remove_anime(&self, anime_id: i32) -> Result<()> {
+        use schema::{anime, anime_slots, tournament_anime};
+
+        // (rows removed, a tournament that still has the anime)
+        let (removed, in_tournament) = with_connection!(self, |conn| conn.transaction::<_, diesel::result::Error, _>(|| {
+            let in_tournament = tournament_anime::table.filter(tournament_anime::anime.eq(anime_id))
+                .select(tournament_anime::tournament)
+                .first::<i32>(conn)
+                .optional()?;
+            if in_tournament.is_some() {
+                return Ok((0, in_tournament));
+            }
+
+            diesel::delete(anime_slots::table.filter(anime_slots::anime.eq(anime_id)))
+                .execute(conn)?;
+            let removed = diesel::delete(anime::table.filter(anime::id.eq(anime_id)))
+                .execute(conn)?;
+            Ok((removed, None))
+        }))?;
+
+        if let Some(tournament_id) = in_tournament {
+            return Err(TournamentError::Invalid(format!("anime #{} is in tournament #{}, so it can't be removed", anime_id, tournament_id)));
+        }
+        if removed == 0 {
+            return Err(TournamentError::not_found("anime", anime_id));
+        }
+        Ok(())
+    }
+
+    fn add_slot(&self, new_slot: &NewSlot) -> Result<Slot> {
+        use schema::slots;
+
+        with_connection!(self, |conn| diesel::insert_into(slots::table)
+            .values(new_slot)
+            .execute(conn))?;
+
+        let slot_id = self.get_last_inserted_id()?;
+        self.get_slot(slot_id)
+    }
+
+    fn get_slots(&self) -> Result<Vec<Slot>> {
+        use schema::slots::dsl::*;
+
+        Ok(with_connection!(self, |conn| slots.order((position, id))
+            .load::<Slot>(conn))?)
+    }
+
+    fn get_slot(&self, slot_id: i32) -> Result<Slot> {
+        use schema::slots::dsl::*;
+
+        with_connection!(self, |conn| slots.filter(id.eq(slot_id))
+            .first::<Slot>(conn)
+            .optional())?
+            .ok_or_else(|| TournamentError::not_found("slot", slot_id))
+    }
+
+    fn update_slot(&self, slot: &Slot) -> Result<()> {
+        use schema::slots::dsl::*;
+
+        let updated = with_connection!(self, |conn| diesel::update(slots.filter(id.eq(slot.id)))
+            .set((
+                name.eq(&slot.name),
+                position.eq(slot.position),
+                duration.eq(slot.duration),
+                max_episodes.eq(slot.max_episodes),
+            ))
+            .execute(conn))?;
+
+        if updated == 0 {
+            return Err(TournamentError::not_found("slot", slot.id));
+        }
+        Ok(())
+    }
+
+    fn remove_slot(&self, slot_id: i32) -> Result<()> {
+        use schema::{anime_slots, slots, tournaments};
+
+        // (rows removed, a tournament that still uses the slot)
+        let (removed, in_tournament) = with_connection!(self, |conn| conn.transaction::<_, diesel::result::Error, _>(|| {
+            let in_tournament = tournaments::table.filter(tournaments::slot.eq(slot_id))
+                .select(tournaments::id)
+                .first::<i32>(conn)
+                .optional()?;
+            if in_tournament.is_some() {
+                return Ok((0, in_tournament));
+            }
+
+            diesel::delete(anime_slots::table.filter(anime_slots::slot.eq(slot_id)))
+                .execute(conn)?;
+            let removed = diesel::delete(slots::table.filter(slots::id.eq(slot_id)))
+                .execute(conn)?;
+            Ok((removed, None))
+        }))?;
+
+        if let Some(tournament_id) = in_tournament {
+            return Err(TournamentError::Invalid(format!("slot #{} is used by tournament #{}, so it can't be removed", slot_id, tournament_id)));
+        }
+        if removed == 0 {
+            return Err(TournamentError::not_found("slot", slot_id));
+        }
+        Ok(())
+    }
+
+    fn get_anime_slots(&self, anime_id: i32) -> Result<Vec<Slot>> {
+        use schema::{anime_slots, slots};
+
+        let slot_ids: Vec<i32> = with_connection!(self, |conn| anime_slots::table
+            .filter(anime_slots::anime.eq(anime_id))
+            .select(anime_slots::slot)
+            .load::<i32>(conn))?;
+
+        Ok(with_connection!(self, |conn| slots::table.filter(slots::id.eq_any(&slot_ids))
+            .order((slots::position, slots::id))
+            .load::<Slot>(conn))?)
+    }
+
+    fn set_anime_slots(&self, anime_id: i32, slot_ids: &[i32]) -> Result<()> {
+        use schema::anime_slots;
+
+        with_connection!(self, |conn| conn.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(anime_slots::table.filter(anime_slots::anime.eq(anime_id)))
+                .execute(conn)?;
+            for slot_id in slot_ids {
+                diesel::insert_into(anime_slots::table)
+                    .values(&AnimeSlot { anime: anime_id, slot: *slot_id })
+                    .execute(conn)?;
+            }
+            Ok(())
+        }))?;
+        Ok(())
+    }
+
+    fn add_member(&self, name: &str) -> Result<Member> {
+        use schema::members;
+
+        with_connection!(self, |conn| diesel::insert_into(members::table)
+            .values(members::name.eq(name))
+            .execute(conn))?;
+
+        let member_id = self.get_last_inserted_id()?;
+        self.get_member(member_id)
+    }
+
+    fn get_members(&self) -> Result<Vec<Member>> {
+        use schema::members::dsl::*;
+
+        Ok(with_connection!(self, |conn| members.order(id)
+            .load::<Member>(conn))?)
+    }
+
+    fn get_member(&self, member_id: i32) -> Result<Member> {
+        use schema::members::dsl::*;
+
+        with_connection!(self, |conn| members.filter(id.eq(member_id))
+            .first::<Member>(conn)
+            .optional())?
+            .ok_or_else(|| TournamentError::not_found("member", member_id))
+    }
+
+    fn add_rating(&self, new_rating: &NewRating) -> Result<()> {
+        use schema::ratings;
+
+        with_connection!(self, |conn| diesel::insert_into(ratings::table)
+            .values(new_rating)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn get_ratings(&self, for_system: System) -> Result<Vec<Rating>> {
+        use schema::ratings::dsl::*;
+
+        Ok(with_connection!(self, |conn| ratings.filter(system.eq(for_system))
+            .order(id)
+            .load::<Rating>(conn))?)
+    }
+
+    fn clear_ratings(&self) -> Result<()> {
+        use schema::ratings;
+
+        with_connection!(self, |conn| diesel::delete(ratings::table)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn add_bracket_match(&self, bracket_match: &BracketMatch) -> Result<()> {
+        use schema::bracket_matches;
+
+        with_connection!(self, |conn| diesel::insert_into(bracket_matches::table)
+            .values(bracket_match)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn get_bracket_matches(&self, t: &Tournament) -> Result<Vec<BracketMatch>> {
+        use schema::bracket_matches::dsl::*;
+
+        Ok(with_connection!(self, |conn| bracket_matches.filter(tournament.eq(t.id))
+            .order(number)
+            .load::<BracketMatch>(conn))?)
+    }
+
+    fn remove_bracket_matches(&self, t: &Tournament) -> Result<()> {
+        use schema::bracket_matches::dsl::*;
+
+        with_connection!(self, |conn| diesel::delete(bracket_matches.filter(tournament.eq(t.id)))
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn add_approval_ballot(&self, ballot: &ApprovalBallot) -> Result<()> {
+        use schema::approval_ballots;
+
+        with_connection!(self, |conn| diesel::insert_into(approval_ballots::table)
+            .values(ballot)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn get_approval_ballots(&self, t: &Tournament) -> Result<Vec<ApprovalBallot>> {
+        use schema::approval_ballots::dsl::*;
+
+        Ok(with_connection!(self, |conn| approval_ballots.filter(tournament.eq(t.id))
+            .order((voter, anime))
+            .load::<ApprovalBallot>(conn))?)
+    }
+
+    fn add_ranked_ballot(&self, ballot: &RankedBallot) -> Result<()> {
+        use schema::ranked_ballots;
+
+        with_connection!(self, |conn| diesel::insert_into(ranked_ballots::table)
+            .values(ballot)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn get_ranked_ballots(&self, t: &Tournament) -> Result<Vec<RankedBallot>> {
+        use schema::ranked_ballots::dsl::*;
+
+        Ok(with_connection!(self, |conn| ranked_ballots.filter(tournament.eq(t.id))
+            .order((voter, position))
+            .load::<RankedBallot>(conn))?)
+    }
+
+    fn add_room(&self, room: &Room) -> Result<()> {
+        use schema::rooms;
+
+        with_connection!(self, |conn| diesel::insert_into(rooms::table)
+            .values(room)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn update_room(&self, room: &Room) -> Result<()> {
+        use schema::rooms::dsl::*;
+
+        let updated = with_connection!(self, |conn| diesel::update(rooms.filter(tournament.eq(room.tournament)))
+            .set((
+                matchup.eq(room.matchup),
+                opened_at.eq(room.opened_at),
+            ))
+            .execute(conn))?;
+
+        if updated == 0 {
+            return Err(TournamentError::not_found_named("room", &room.code));
+        }
+        Ok(())
+    }
+
+    fn get_room(&self, t: &Tournament) -> Result<Option<Room>> {
+        use schema::rooms::dsl::*;
+
+        Ok(with_connection!(self, |conn| rooms.filter(tournament.eq(t.id))
+            .first::<Room>(conn)
+            .optional())?)
+    }
+
+    fn find_room(&self, room_code: &str) -> Result<Room> {
+        use schema::rooms::dsl::*;
+
+        with_connection!(self, |conn| rooms.filter(code.eq(room_code))
+            .first::<Room>(conn)
+            .optional())?
+            .ok_or_else(|| TournamentError::not_found_named("room", room_code))
+    }
+
+    fn add_room_member(&self, room_member: &RoomMember) -> Result<()> {
+        use schema::room_members;
+
+        with_connection!(self, |conn| diesel::insert_into(room_members::table)
+            .values(room_member)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn get_room_members(&self, room: &Room) -> Result<Vec<Member>> {
+        use schema::{members, room_members};
+
+        let member_ids: Vec<i32> = with_connection!(self, |conn| room_members::table
+            .filter(room_members::tournament.eq(room.tournament))
+            .select(room_members::member)
+            .load::<i32>(conn))?;
+
+        Ok(with_connection!(self, |conn| members::table.filter(members::id.eq_any(&member_ids))
+            .order(members::id)
+            .load::<Member>(conn))?)
+    }
+
+    fn add_room_ballot(&self, ballot: &RoomBallot) -> Result<()> {
+        use schema::room_ballots;
+
+        with_connection!(self, |conn| diesel::insert_into(room_ballots::table)
+            .values(ballot)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn get_room_ballots(&self, room: &Room) -> Result<Vec<RoomBallot>> {
+        use schema::room_ballots::dsl::*;
+
+        Ok(with_connection!(self, |conn| room_ballots.filter(tournament.eq(room.tournament))
+            .order((matchup, cast_at))
+            .load::<RoomBallot>(conn))?)
+    }
+
+    fn create_tournament(&self, new_tournament: &NewTournament) -> Result<Tournament> {
+        use schema::tournaments;
+
+        with_connection!(self, |conn| diesel::insert_into(tournaments::table)
+            .values(new_tournament)
+            .execute(conn))?;
+
+        let tournament_id = self.get_last_inserted_id()?;
+        self.get_tournament(tournament_id)
+    }
+
+    fn update_tournament(&self, t: &Tournament) -> Result<()> {
+        use schema::tournaments::dsl::*;
+
+        let updated = with_connection!(self, |conn| diesel::update(tournaments.filter(id.eq(t.id)))
+            .set((
+                title.eq(&t.title),
+                status.eq(t.status),
+                finished_at.eq(t.finished_at),
+            ))
+            .execute(conn))?;
+
+        if updated == 0 {
+            return Err(TournamentError::not_found("tournament", t.id));
+        }
+        Ok(())
+    }
+
+    fn get_tournaments(&self) -> Result<Vec<Tournament>> {
+        use schema::tournaments::dsl::*;
+
+        Ok(with_connection!(self, |conn| tournaments.order(id)
+            .load::<Tournament>(conn))?)
+    }
+
+    fn get_tournament(&self, tournament_id: i32) -> Result<Tournament> {
+        use schema::tournaments::dsl::*;
+
+        with_connection!(self, |conn| tournaments.filter(id.eq(tournament_id))
+            .first::<Tournament>(conn)
+            .optional())?
+            .ok_or_else(|| TournamentError::not_found("tournament", tournament_id))
+    }
+
+    fn get_tournament_anime(&self, t: &Tournament) -> Result<Vec<Anime>> {
+        use schema::{anime, tournament_anime};
+
+        let anime_ids: Vec<i32> = with_connection!(self, |conn| tournament_anime::table
+            .filter(tournament_anime::tournament.eq(t.id))
+            .select(tournament_anime::anime)
+            .load::<i32>(conn))?;
+
+        Ok(with_connection!(self, |conn| anime::table.filter(anime::id.eq_any(&anime_ids))
+            .load::<Anime>(conn))?)
+    }
+
+    fn add_anime_to_tournament(&self, t: &Tournament, a: &Anime) -> Result<()> {
+        use schema::tournament_anime;
+
+        let new_tournament_anime = TournamentAnime {
+            tournament: t.id,
+            anime: a.id
+        };
+        with_connection!(self, |conn| diesel::insert_into(tournament_anime::table)
+            .values(&new_tournament_anime)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn get_tournament_members(&self, t: &Tournament) -> Result<Vec<Member>> {
+        use schema::{members, tournament_members};
+
+        let member_ids: Vec<i32> = with_connection!(self, |conn| tournament_members::table
+            .filter(tournament_members::tournament.eq(t.id))
+            .select(tournament_members::member)
+            .load::<i32>(conn))?;
+
+        Ok(with_connection!(self, |conn| members::table.filter(members::id.eq_any(&member_ids))
+            .order(members::id)
+            .load::<Member>(conn))?)
+    }
+
+    fn add_member_to_tournament(&self, t: &Tournament, m: &Member) -> Result<()> {
+        use schema::tournament_members;
+
+        let new_tournament_member = TournamentMember {
+            tournament: t.id,
+            member: m.id
+        };
+        with_connection!(self, |conn| diesel::insert_into(tournament_members::table)
+            .values(&new_tournament_member)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn get_tournament_ballots(&self, t: &Tournament) -> Result<Vec<Ballot>> {
+        use schema::decisions::dsl::*;
+
+        Ok(with_connection!(self, |conn| decisions.filter(tournament.eq(t.id))
+            .order(number)
+            .load::<Ballot>(conn))?)
+    }
+
+    fn insert_ballot(&self, ballot: &Ballot) -> Result<()> {
+        use schema::decisions;
+
+        with_connection!(self, |conn| diesel::insert_into(decisions::table)
+            .values(ballot)
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn remove_ballots(&self, decision: &Decision) -> Result<()> {
+        use schema::decisions::dsl::*;
+
+        let (a, b) = (decision.left_anime, decision.right_anime);
+        with_connection!(self, |conn| diesel::delete(decisions
+                .filter(tournament.eq(decision.tournament))
+                .filter(meeting.eq(decision.meeting))
+                .filter(left_anime.eq(a).and(right_anime.eq(b))
+                    .or(left_anime.eq(b).and(right_anime.eq(a)))))
+            .execute(conn))?;
+        Ok(())
+    }
+
+    fn remove_ballot(&self, ballot: &Ballot) -> Result<()> {
+        use schema::decisions::dsl::*;
+
+        with_connection!(self, |conn| diesel::delete(decisions
+                .filter(tournament.eq(ballot.tournament))
+                .filter(left_anime.eq(ballot.left_anime))
+                .filter(right_anime.eq(ballot.right_anime))
+                .filter(voter.eq(ballot.voter))
+                .filter(meeting.eq(ballot.meeting)))
+            .execute(conn))?;
+        Ok(())
+    }
+}
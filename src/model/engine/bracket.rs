@@ -0,0 +1,366 @@
+// Single and double elimination brackets.
+//
+// The matches are built once from the seeds and saved, and each one is played
+// as soon as both of its sides are known, in the order they were built. Two
+// anime can meet again, such as in the final of a double elimination, and each
+// meeting is asked about afresh. A double elimination's final is played a
+// second time if the winners' bracket side loses it, so both have lost once.
+
+use super::super::models::*;
+use super::beats_at;
+
+// the order seeds are placed down a bracket of `size`, so that 1 and 2 can only
+// meet in the final: 1 8 4 5 2 7 3 6 for 8
+fn seed_order(size: usize) -> Vec<usize> {
+    let mut order = vec![1];
+    while order.len() < size {
+        let n = order.len() * 2 + 1;
+        order = order.iter().flat_map(|seed| vec![*seed, n - *seed]).collect();
+    }
+    order
+}
+
+struct Builder {
+    tournament: i32,
+    matches: Vec<BracketMatch>,
+}
+
+impl Builder {
+    fn add(&mut self, bracket: Bracket, round: i32, left: Source, right: Source) -> i32 {
+        let number = self.matches.len() as i32 + 1;
+        let mut bracket_match = BracketMatch {
+            tournament: self.tournament,
+            number,
+            bracket,
+            round,
+            left_anime: None,
+            left_winner_of: None,
+            left_loser_of: None,
+            right_anime: None,
+            right_winner_of: None,
+            right_loser_of: None,
+        };
+        bracket_match.set_left(left);
+        bracket_match.set_right(right);
+        self.matches.push(bracket_match);
+        number
+    }
+
+    // Play the sides off in twos, returning where the winners come from and the
+    // numbers of the new matches. An odd one out has a bye to the next round.
+    fn pair_up(&mut self, bracket: Bracket, round: i32, sides: &[Source]) -> (Vec<Source>, Vec<i32>) {
+        let mut winners = vec![];
+        let mut numbers = vec![];
+        for pair in sides.chunks(2) {
+            match pair {
+                [left, right] => {
+                    let number = self.add(bracket, round, *left, *right);
+                    winners.push(Source::Winner(number));
+                    numbers.push(number);
+                },
+                [bye] => winners.push(*bye),
+                _ => ()
+            }
+        }
+        (winners, numbers)
+    }
+}
+
+// Build the matches for the anime in seed order, best first. Seeds left without
+// an opponent, when there aren't a power of two of them, get a bye through the
+// first round. With `double` the losers of the winners' bracket get a second
+// chance in a losers' bracket, whose winner meets the winners' one in the
+// final, and again in a bracket reset if it wins.
+pub fn build(tournament: i32, seeds: &[i32], double: bool) -> Vec<BracketMatch> {
+    let mut builder = Builder { tournament, matches: vec![] };
+    if seeds.len() < 2 {
+        return builder.matches;
+    }
+
+    let size = seeds.len().next_power_of_two();
+    let mut sides = vec![];
+    for pair in seed_order(size).chunks(2) {
+        match (seeds.get(pair[0] - 1), seeds.get(pair[1] - 1)) {
+            (Some(a), Some(b)) => {
+                let number = builder.add(Bracket::Winners, 1, Source::Anime(*a), Source::Anime(*b));
+                sides.push(Source::Winner(number));
+            },
+            (Some(a), None) | (None, Some(a)) => sides.push(Source::Anime(*a)),
+            (None, None) => ()
+        }
+    }
+
+    let mut round = 1;
+    let mut losers: Vec<Source> = vec![];
+    let mut losers_round = 0;
+    let mut new_losers: Vec<Source> = builder.matches.iter().map(|m| Source::Loser(m.number)).collect();
+    loop {
+        if double && !new_losers.is_empty() {
+            if losers.is_empty() {
+                losers = new_losers;
+            } else {
+                // the losers dropping down each meet one already in the losers' bracket,
+                // in reverse order so they don't meet the same anime again straight away
+                new_losers.reverse();
+                let mut mixed = vec![];
+                for i in 0..losers.len().max(new_losers.len()) {
+                    mixed.extend(losers.get(i));
+                    mixed.extend(new_losers.get(i));
+                }
+                losers_round += 1;
+                losers = builder.pair_up(Bracket::Losers, losers_round, &mixed).0;
+            }
+
+            // then play them down to as many as will drop in from the next round
+            let coming = if sides.len() > 1 { sides.len() / 2 } else { 1 };
+            while losers.len() > coming {
+                losers_round += 1;
+                losers = builder.pair_up(Bracket::Losers, losers_round, &losers).0;
+            }
+        }
+
+        if sides.len() < 2 {
+            break;
+        }
+        round += 1;
+        let (winners, numbers) = builder.pair_up(Bracket::Winners, round, &sides);
+        sides = winners;
+        new_losers = numbers.iter().map(|number| Source::Loser(*number)).collect();
+    }
+
+    if let (true, Some(champion), Some(challenger)) = (double, sides.first(), losers.first()) {
+        let first = builder.add(Bracket::Final, round + 1, *champion, *challenger);
+        builder.add(Bracket::Final, round + 1, Source::Loser(first), Source::Winner(first));
+    }
+    builder.matches
+}
+
+// the number of the first final, when `m` is the bracket reset after it
+fn reset_of(m: &BracketMatch) -> Option<i32> {
+    match (m.bracket, m.left(), m.right()) {
+        (Bracket::Final, Source::Loser(a), Source::Winner(b)) if a == b => Some(a),
+        _ => None
+    }
+}
+
+// each match's two anime and its winner, as far as they're known
+pub fn play(matches: &[BracketMatch], decisions: &[Decision]) -> Vec<(Option<i32>, Option<i32>, Option<i32>)> {
+    let mut played: Vec<(Option<i32>, Option<i32>, Option<i32>)> = vec![];
+
+    for m in matches {
+        let resolve = |source: Source| -> Option<i32> {
+            let (number, want_winner) = match source {
+                Source::Anime(anime) => return Some(anime),
+                Source::Winner(number) => (number, true),
+                Source::Loser(number) => (number, false),
+            };
+            let i = matches.iter().position(|m| m.number == number)?;
+            let (left, right, winner) = *played.get(i)?;
+            let winner = winner?;
+            if want_winner {
+                Some(winner)
+            } else if left == Some(winner) {
+                right
+            } else {
+                left
+            }
+        };
+
+        // a bracket reset is only played when the challenger won the first final
+        let needed = match reset_of(m) {
+            Some(first) => matches.iter().position(|m| m.number == first)
+                .and_then(|i| played.get(i))
+                .is_some_and(|(_, right, winner)| winner.is_some() && winner == right),
+            None => true
+        };
+        let (left, right) = if needed { (resolve(m.left()), resolve(m.right())) } else { (None, None) };
+        let winner = match (left, right) {
+            (Some(a), Some(b)) => {
+                let meeting = times_met(&played, a, b);
+                beats_at(decisions, meeting, a, b).map(|a_won| if a_won { a } else { b })
+            },
+            _ => None
+        };
+        played.push((left, right, winner));
+    }
+    played
+}
+
+// how many of the matches played so far had `a` and `b` in them
+fn times_met(played: &[(Option<i32>, Option<i32>, Option<i32>)], a: i32, b: i32) -> i32 {
+    played.iter()
+        .filter(|(left, right, _)| (*left, *right) == (Some(a), Some(b)) || (*left, *right) == (Some(b), Some(a)))
+        .count() as i32
+}
+
+// which time `a` and `b` meet in the match they're ready to play, if they are
+pub fn meeting(matches: &[BracketMatch], decisions: &[Decision], a: i32, b: i32) -> Option<i32> {
+    let played = play(matches, decisions);
+    let i = played.iter().position(|(left, right, winner)| winner.is_none()
+        && ((*left, *right) == (Some(a), Some(b)) || (*left, *right) == (Some(b), Some(a))))?;
+    Some(times_met(&played[..i], a, b))
+}
+
+pub fn next_pair(matches: &[BracketMatch], decisions: &[Decision]) -> Option<(i32, i32)> {
+    play(matches, decisions).into_iter()
+        .find_map(|(left, right, winner)| match (left, right, winner) {
+            (Some(a), Some(b), None) => Some((a, b)),
+            _ => None
+        })
+}
+
+// the number of the match each anime went out in, for those that are out
+fn knocked_out(matches: &[BracketMatch], decisions: &[Decision]) -> Vec<(i32, usize)> {
+    let played = play(matches, decisions);
+    let mut out = vec![];
+    for (i, (m, (left, right, winner))) in matches.iter().zip(played.iter()).enumerate() {
+        // a loser that doesn't go on to another match is out, as is the challenger
+        // beaten in the first final, as there's no bracket reset then
+        let goes_on = matches.iter().zip(played.iter()).any(|(other, (other_left, _, _))|
+            (other.left() == Source::Loser(m.number) || other.right() == Source::Loser(m.number))
+                && (reset_of(other).is_none() || other_left.is_some()));
+        if let (Some(left), Some(right), Some(winner), false) = (left, right, winner, goes_on) {
+            out.push((if winner == left { *right } else { *left }, i));
+        }
+    }
+    out
+}
+
+pub fn remaining_anime(matches: &[BracketMatch], anime: &[i32], decisions: &[Decision]) -> Vec<i32> {
+    let out = knocked_out(matches, decisions);
+    anime.iter().filter(|a| !out.iter().any(|(b, _)| b == *a)).copied().collect()
+}
+
+pub fn winner(matches: &[BracketMatch], anime: &[i32], decisions: &[Decision]) -> Option<i32> {
+    let played = play(matches, decisions);
+    // without a bracket reset the first final decides it
+    let last = match (matches.last().and_then(reset_of), played.last()) {
+        (Some(_), Some((None, _, _))) => played.len().checked_sub(2).and_then(|i| played.get(i)),
+        _ => played.last()
+    };
+    match last {
+        Some((_, _, winner)) => *winner,
+        None if anime.len() == 1 => anime.first().copied(),
+        None => None
+    }
+}
+
+// the anime still in first, then the rest by how late they went out
+pub fn ranking(matches: &[BracketMatch], anime: &[i32], decisions: &[Decision]) -> Vec<i32> {
+    let out = knocked_out(matches, decisions);
+    let went_out = |a: &i32| out.iter().find(|(b, _)| b == a).map(|(_, i)| *i);
+
+    let mut ranked = anime.to_vec();
+    ranked.sort_by_key(|a| match went_out(a) {
+        Some(i) => (1, -(i as i64)),
+        None => (0, 0)
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::decided;
+
+    fn at(meeting: i32, decision: Decision) -> Decision {
+        Decision { meeting, ..decision }
+    }
+
+    #[test]
+    fn seeds_are_spread_so_the_top_two_meet_last() {
+        assert_eq!(seed_order(8), vec![1, 8, 4, 5, 2, 7, 3, 6]);
+    }
+
+    #[test]
+    fn the_top_seeds_get_the_byes() {
+        let matches = build(1, &[1, 2, 3, 4, 5], false);
+        assert_eq!(matches.len(), 4);
+        assert_eq!((matches[0].left(), matches[0].right()), (Source::Anime(4), Source::Anime(5)));
+        assert!(matches[1..].iter().all(|m| m.round > 1));
+    }
+
+    #[test]
+    fn a_double_elimination_has_a_match_for_each_loss() {
+        for n in 2..=9 {
+            let seeds: Vec<i32> = (1..=n).collect();
+            let matches = build(1, &seeds, true);
+            // counting the bracket reset
+            assert_eq!(matches.len(), 2 * n as usize - 1, "{} seeds", n);
+            assert_eq!(matches.last().map(|m| m.bracket), Some(Bracket::Final));
+        }
+    }
+
+    #[test]
+    fn the_winners_go_through_to_a_winner() {
+        let anime = [1, 2, 3, 4];
+        let matches = build(1, &anime, false);
+        let mut decisions = vec![];
+        while let Some((a, b)) = next_pair(&matches, &decisions) {
+            decisions.push(decided(a, b, if a < b { Pick::Left } else { Pick::Right }));
+        }
+        assert_eq!(decisions.len(), 3);
+        assert_eq!(winner(&matches, &anime, &decisions), Some(1));
+        assert_eq!(ranking(&matches, &anime, &decisions)[..2], [1, 2]);
+    }
+
+    #[test]
+    fn a_rematch_is_asked_again() {
+        let anime = [1, 2, 3, 4];
+        let matches = build(1, &anime, true);
+        let mut decisions = vec![
+            decided(1, 4, Pick::Left),
+            decided(2, 3, Pick::Left),
+            decided(4, 3, Pick::Right),
+            decided(1, 2, Pick::Left),
+        ];
+
+        // 2 and 3 meet again in the losers' bracket
+        assert_eq!(next_pair(&matches, &decisions), Some((3, 2)));
+        assert_eq!(meeting(&matches, &decisions, 2, 3), Some(1));
+        assert_eq!(meeting(&matches, &decisions, 1, 3), None);
+
+        decisions.push(at(1, decided(3, 2, Pick::Left)));
+        assert_eq!(next_pair(&matches, &decisions), Some((1, 3)));
+        assert_eq!(meeting(&matches, &decisions, 1, 3), Some(0));
+
+        decisions.push(decided(1, 3, Pick::Left));
+        assert_eq!(next_pair(&matches, &decisions), None);
+        assert_eq!(winner(&matches, &anime, &decisions), Some(1));
+        assert_eq!(remaining_anime(&matches, &anime, &decisions), vec![1]);
+        assert_eq!(ranking(&matches, &anime, &decisions)[..2], [1, 3]);
+    }
+
+    #[test]
+    fn the_final_is_played_again_if_the_challenger_wins_it() {
+        let anime = [1, 2, 3, 4];
+        let matches = build(1, &anime, true);
+        let mut decisions = vec![
+            decided(1, 4, Pick::Left),
+            decided(2, 3, Pick::Left),
+            decided(4, 3, Pick::Right),
+            decided(1, 2, Pick::Left),
+            at(1, decided(3, 2, Pick::Left)),
+            decided(1, 3, Pick::Right),
+        ];
+
+        // both have lost once now, so they meet again
+        assert_eq!(winner(&matches, &anime, &decisions), None);
+        assert_eq!(next_pair(&matches, &decisions), Some((1, 3)));
+        assert_eq!(meeting(&matches, &decisions, 1, 3), Some(1));
+        assert_eq!(remaining_anime(&matches, &anime, &decisions), vec![1, 3]);
+
+        decisions.push(at(1, decided(1, 3, Pick::Right)));
+        assert_eq!(next_pair(&matches, &decisions), None);
+        assert_eq!(winner(&matches, &anime, &decisions), Some(3));
+        assert_eq!(ranking(&matches, &anime, &decisions)[..2], [3, 1]);
+    }
+
+    #[test]
+    fn a_drawn_match_is_played_again() {
+        let matches = build(1, &[1, 2], false);
+        let decisions = vec![decided(1, 2, Pick::Undecided)];
+        assert_eq!(next_pair(&matches, &decisions), Some((1, 2)));
+        assert_eq!(meeting(&matches, &decisions, 1, 2), Some(0));
+    }
+}
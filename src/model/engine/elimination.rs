@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use super::super::models::*;
+use super::pairing::{PairingStrategy, Round};
+
+// the anime that haven't lost a decision yet
+pub fn remaining_anime(anime: &[i32], decisions: &[Decision]) -> Vec<i32> {
+    let mut remaining: Vec<i32> = anime.to_vec();
+
+    // exclude anime based on picks
+    let mut remove: Vec<i32> = vec![];
+    for decision in decisions {
+        match Pick::to_pick(decision.pick) {
+            Pick::Left => remove.push(decision.right_anime),
+            Pick::Right => remove.push(decision.left_anime),
+            Pick::Undecided => ()
+        };
+    }
+
+    remaining.retain(|item| !remove.contains(item));
+    remaining
+}
+
+// counted in half picks, so a draw is worth half a win to both anime
+pub fn count_picks(decisions: &[Decision], anime: &[i32]) -> HashMap<i32, i32> {
+    let mut num_picks: HashMap<i32, i32> = HashMap::new();
+    for anime in anime {
+        num_picks.insert(*anime, 0);
+    }
+
+    for decision in decisions {
+        let (picks, points) = match Pick::to_pick(decision.pick) {
+            Pick::Left => (vec![decision.left_anime], 2),
+            Pick::Right => (vec![decision.right_anime], 2),
+            Pick::Undecided => (vec![decision.left_anime, decision.right_anime], 1)
+        };
+        for pick in picks {
+            if let Some(num) = num_picks.get_mut(&pick) {
+                *num += points;
+            }
+        }
+    }
+    num_picks
+}
+
+pub fn next_pair(anime: &[i32], decisions: &[Decision], ratings: &HashMap<i32, f64>, seed: u64, strategy: &dyn PairingStrategy) -> Option<(i32, i32)> {
+    let remaining = remaining_anime(anime, decisions);
+    if remaining.len() < 2 {
+        return None
+    }
+
+    strategy.next_pair(&Round { anime, remaining: &remaining, decisions, ratings, seed })
+}
+
+pub fn winner(anime: &[i32], decisions: &[Decision]) -> Option<i32> {
+    let anime: Vec<i32> = remaining_anime(anime, decisions);
+    if anime.len() > 1 {
+        return None;
+    }
+
+    anime.first().copied()
+}
+
+// the anime still in the running, then the rest by how many picks they won before going out
+pub fn ranking(anime: &[i32], decisions: &[Decision]) -> Vec<i32> {
+    let remaining = remaining_anime(anime, decisions);
+    let num_picks = count_picks(decisions, anime);
+
+    let mut ranked = anime.to_vec();
+    ranked.sort_by_key(|a| (!remaining.contains(a), -num_picks[a]));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::decided;
+    use super::super::pairing::LowestPick;
+
+    #[test]
+    fn losers_are_knocked_out_but_a_draw_keeps_both() {
+        let decisions = vec![decided(1, 2, Pick::Left), decided(3, 4, Pick::Undecided)];
+        assert_eq!(remaining_anime(&[1, 2, 3, 4], &decisions), vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn a_draw_is_half_a_win() {
+        let decisions = vec![decided(1, 2, Pick::Left), decided(1, 3, Pick::Undecided)];
+        let picks = count_picks(&decisions, &[1, 2, 3]);
+        assert_eq!(picks[&1], 3);
+        assert_eq!(picks[&2], 0);
+        assert_eq!(picks[&3], 1);
+    }
+
+    #[test]
+    fn the_last_anime_standing_wins() {
+        let anime = [1, 2, 3];
+        let mut decisions = vec![decided(1, 2, Pick::Right)];
+        assert_eq!(winner(&anime, &decisions), None);
+
+        decisions.push(decided(2, 3, Pick::Left));
+        assert_eq!(winner(&anime, &decisions), Some(2));
+        assert_eq!(next_pair(&anime, &decisions, &HashMap::new(), 1, &LowestPick), None);
+    }
+
+    #[test]
+    fn pairs_are_only_from_the_remaining_anime() {
+        let anime = [1, 2, 3, 4];
+        let decisions = vec![decided(1, 2, Pick::Left)];
+        let (a, b) = next_pair(&anime, &decisions, &HashMap::new(), 1, &LowestPick).unwrap();
+        assert_ne!(a, b);
+        assert!(a != 2 && b != 2);
+    }
+
+    #[test]
+    fn ranking_puts_the_remaining_first_then_by_picks() {
+        let decisions = vec![
+            decided(1, 2, Pick::Left),
+            decided(3, 4, Pick::Left),
+            decided(1, 3, Pick::Left),
+        ];
+        assert_eq!(ranking(&[1, 2, 3, 4], &decisions), vec![1, 3, 2, 4]);
+    }
+}
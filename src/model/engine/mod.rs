@@ -0,0 +1,212 @@
+// The tournament logic, kept apart from storage.
+//
+// Each mode works only from the tournament's anime and the decisions made so far,
+// and for brackets the matches they were seeded into, so a tournament can always
+// be picked up again from what's in the database.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::models::*;
+
+pub mod bracket;
+pub mod elimination;
+pub mod pairing;
+pub mod ranking;
+pub mod round_robin;
+pub mod swiss;
+pub mod top_k;
+pub mod votes;
+
+// the decision for `a` and `b`, whichever way round they were asked
+pub fn find_decision(decisions: &[Decision], a: i32, b: i32) -> Option<&Decision> {
+    find_meeting(decisions, 0, a, b)
+}
+
+// the decision for a later time `a` and `b` met, where a bracket has them meet again
+pub fn find_meeting(decisions: &[Decision], meeting: i32, a: i32, b: i32) -> Option<&Decision> {
+    decisions.iter().find(|decision| decision.meeting == meeting && (
+        (decision.left_anime, decision.right_anime) == (a, b) || (decision.left_anime, decision.right_anime) == (b, a)
+    ))
+}
+
+// how `a` did against `b`, if they've been compared: Equal is a draw
+pub fn compare(decisions: &[Decision], a: i32, b: i32) -> Option<Ordering> {
+    compare_at(decisions, 0, a, b)
+}
+
+pub fn compare_at(decisions: &[Decision], meeting: i32, a: i32, b: i32) -> Option<Ordering> {
+    let decision = find_meeting(decisions, meeting, a, b)?;
+    let picked = match Pick::to_pick(decision.pick) {
+        Pick::Left => decision.left_anime,
+        Pick::Right => decision.right_anime,
+        Pick::Undecided => return Some(Ordering::Equal)
+    };
+    Some(if picked == a { Ordering::Greater } else { Ordering::Less })
+}
+
+// whether `a` was picked over `b`, if one of them was picked
+pub fn beats(decisions: &[Decision], a: i32, b: i32) -> Option<bool> {
+    beats_at(decisions, 0, a, b)
+}
+
+pub fn beats_at(decisions: &[Decision], meeting: i32, a: i32, b: i32) -> Option<bool> {
+    match compare_at(decisions, meeting, a, b)? {
+        Ordering::Equal => None,
+        ordering => Some(ordering == Ordering::Greater)
+    }
+}
+
+// where an anime stands in a Swiss or round robin tournament
+#[derive(Debug,Clone)]
+pub struct Standing {
+    pub anime: i32,
+    // 1 for a win or a bye, a half for a draw
+    pub score: f64,
+    pub wins: i32,
+    pub draws: i32,
+    pub losses: i32,
+    pub byes: i32,
+    // what breaks a tie on score, in the order of the mode's TIEBREAKS
+    pub tiebreaks: Vec<f64>,
+}
+
+impl Standing {
+    // from the anime's score against each opponent
+    fn new(anime: i32, results: &[(i32, f64)], byes: i32, tiebreaks: Vec<f64>) -> Standing {
+        Standing {
+            anime,
+            score: results.iter().map(|(_, score)| score).sum::<f64>() + byes as f64,
+            wins: results.iter().filter(|(_, score)| *score == 1.0).count() as i32,
+            draws: results.iter().filter(|(_, score)| *score == 0.5).count() as i32,
+            losses: results.iter().filter(|(_, score)| *score == 0.0).count() as i32,
+            byes,
+            tiebreaks,
+        }
+    }
+
+    // best first, keeping the order they're in when everything is tied
+    fn sort(standings: &mut [Standing]) {
+        standings.sort_by(|x, y| {
+            let by_score = y.score.partial_cmp(&x.score).unwrap_or(Ordering::Equal);
+            by_score.then_with(|| y.tiebreaks.partial_cmp(&x.tiebreaks).unwrap_or(Ordering::Equal))
+        });
+    }
+}
+
+// the names of the tiebreaks in a mode's standings
+pub fn tiebreaks(t: &Tournament) -> &'static [&'static str] {
+    match t.mode {
+        Mode::Swiss => &swiss::TIEBREAKS,
+        Mode::RoundRobin => &round_robin::TIEBREAKS,
+        _ => &[]
+    }
+}
+
+fn k(t: &Tournament) -> usize {
+    t.k.unwrap_or(1).max(1) as usize
+}
+
+fn rounds(t: &Tournament, anime: &[i32]) -> usize {
+    match t.rounds {
+        Some(rounds) => rounds.max(1) as usize,
+        None => swiss::default_rounds(anime.len())
+    }
+}
+
+// the anime that can still win
+pub fn remaining_anime(t: &Tournament, anime: &[i32], decisions: &[Decision], matches: &[BracketMatch]) -> Vec<i32> {
+    match t.mode {
+        Mode::Elimination => elimination::remaining_anime(anime, decisions),
+        Mode::Ranking | Mode::Swiss | Mode::RoundRobin => anime.to_vec(),
+        Mode::TopK => top_k::remaining_anime(k(t), anime, decisions),
+        Mode::SingleElimination | Mode::DoubleElimination => bracket::remaining_anime(matches, anime, decisions),
+        Mode::Approval | Mode::Borda | Mode::InstantRunoff => anime.to_vec(),
+    }
+}
+
+// `ratings` are the anime's ratings from earlier tournaments, where a mode can use them
+pub fn next_pair(t: &Tournament, anime: &[i32], decisions: &[Decision], matches: &[BracketMatch], ratings: &HashMap<i32, f64>) -> Option<(i32, i32)> {
+    match t.mode {
+        Mode::Elimination => elimination::next_pair(anime, decisions, ratings, t.seed as u64, &*pairing::strategy(t.pairing)),
+        Mode::Ranking => ranking::next_pair(anime, decisions),
+        Mode::TopK => top_k::next_pair(k(t), anime, decisions),
+        Mode::SingleElimination | Mode::DoubleElimination => bracket::next_pair(matches, decisions),
+        Mode::Swiss => swiss::next_pair(rounds(t, anime), anime, decisions),
+        Mode::RoundRobin => round_robin::next_pair(anime, decisions),
+        // whole ballots have no pairs to ask about
+        Mode::Approval | Mode::Borda | Mode::InstantRunoff => None,
+    }
+}
+
+// Which time `a` and `b` are meeting, if they're to be compared now: only a
+// bracket has the same two meet again, and only in a match that's ready.
+pub fn meeting(t: &Tournament, decisions: &[Decision], matches: &[BracketMatch], a: i32, b: i32) -> Option<i32> {
+    match t.mode {
+        Mode::SingleElimination | Mode::DoubleElimination => bracket::meeting(matches, decisions, a, b),
+        _ => Some(0)
+    }
+}
+
+pub fn winner(t: &Tournament, anime: &[i32], decisions: &[Decision], matches: &[BracketMatch]) -> Option<i32> {
+    match t.mode {
+        Mode::Elimination => elimination::winner(anime, decisions),
+        Mode::Ranking => ranking::winner(anime, decisions),
+        Mode::TopK => top_k::winner(k(t), anime, decisions),
+        Mode::SingleElimination | Mode::DoubleElimination => bracket::winner(matches, anime, decisions),
+        Mode::Swiss => swiss::winner(rounds(t, anime), anime, decisions),
+        Mode::RoundRobin => round_robin::winner(anime, decisions),
+        // counted from the whole ballots instead, see `ballots`
+        Mode::Approval | Mode::Borda | Mode::InstantRunoff => None,
+    }
+}
+
+// the standings so far, for the modes that have them
+pub fn standings(t: &Tournament, anime: &[i32], decisions: &[Decision]) -> Option<Vec<Standing>> {
+    match t.mode {
+        Mode::Swiss => Some(swiss::standings(anime, decisions, &swiss::play(rounds(t, anime), anime, decisions))),
+        Mode::RoundRobin => Some(round_robin::standings(anime, decisions)),
+        _ => None
+    }
+}
+
+// best first
+pub fn ranking(t: &Tournament, anime: &[i32], decisions: &[Decision], matches: &[BracketMatch]) -> Vec<i32> {
+    match t.mode {
+        Mode::Elimination => elimination::ranking(anime, decisions),
+        Mode::Ranking => ranking::ranking(anime, decisions),
+        Mode::TopK => top_k::top_k(k(t), anime, decisions),
+        Mode::SingleElimination | Mode::DoubleElimination => bracket::ranking(matches, anime, decisions),
+        Mode::Swiss => swiss::ranking(rounds(t, anime), anime, decisions),
+        Mode::RoundRobin => round_robin::ranking(anime, decisions),
+        Mode::Approval | Mode::Borda | Mode::InstantRunoff => anime.to_vec(),
+    }
+}
+
+// a decision between two anime, for the tests
+#[cfg(test)]
+pub(crate) fn decided(left_anime: i32, right_anime: i32, pick: Pick) -> Decision {
+    Decision { tournament: 1, left_anime, right_anime, pick: Pick::to_opt(pick), meeting: 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_decision_is_found_either_way_round() {
+        let decisions = vec![decided(1, 2, Pick::Left)];
+        assert!(find_decision(&decisions, 2, 1).is_some());
+        assert_eq!(compare(&decisions, 1, 2), Some(Ordering::Greater));
+        assert_eq!(compare(&decisions, 2, 1), Some(Ordering::Less));
+        assert_eq!(compare(&decisions, 1, 3), None);
+    }
+
+    #[test]
+    fn a_draw_compares_equal_and_beats_neither() {
+        let decisions = vec![decided(1, 2, Pick::Undecided)];
+        assert_eq!(compare(&decisions, 2, 1), Some(Ordering::Equal));
+        assert_eq!(beats(&decisions, 1, 2), None);
+        assert_eq!(beats(&decisions, 2, 1), None);
+    }
+}
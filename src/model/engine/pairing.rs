@@ -0,0 +1,254 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+
+use super::super::bradley_terry;
+use super::super::models::*;
+use super::elimination::count_picks;
+use super::find_decision;
+
+// what a strategy picks the next pair from
+pub struct Round<'a> {
+    pub anime: &'a [i32],
+    // the anime still in, always at least two of them
+    pub remaining: &'a [i32],
+    pub decisions: &'a [Decision],
+    // the anime's ratings from earlier tournaments, if they have any
+    pub ratings: &'a HashMap<i32, f64>,
+    // the tournament's random seed
+    pub seed: u64,
+}
+
+impl<'a> Round<'a> {
+    // Random numbers for this pair: the same for the same tournament after the
+    // same decisions, so a tournament can be replayed question for question.
+    fn rng(&self) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        rng.set_stream(self.decisions.len() as u64);
+        rng
+    }
+
+    // Pairs of the remaining anime that haven't met yet. Those that have met
+    // drew, so they only get a rematch once every pair left has drawn.
+    fn pairs(&self) -> Vec<(i32, i32)> {
+        let mut all = vec![];
+        for (i, a) in self.remaining.iter().enumerate() {
+            for b in &self.remaining[i + 1..] {
+                all.push((*a, *b));
+            }
+        }
+
+        let new: Vec<(i32, i32)> = all.iter()
+            .filter(|(a, b)| find_decision(self.decisions, *a, *b).is_none())
+            .copied()
+            .collect();
+        if new.is_empty() { all } else { new }
+    }
+
+    // the first of `order` that can be paired, with the first after it that it can be paired with
+    fn first_pair(&self, order: &[i32]) -> Option<(i32, i32)> {
+        let pairs = self.pairs();
+        for (i, a) in order.iter().enumerate() {
+            for b in &order[i + 1..] {
+                if pairs.contains(&(*a, *b)) || pairs.contains(&(*b, *a)) {
+                    return Some((*a, *b));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Picks which two of the remaining anime to compare next in an elimination
+/// tournament.
+pub trait PairingStrategy {
+    fn next_pair(&self, round: &Round) -> Option<(i32, i32)>;
+}
+
+pub fn strategy(pairing: Pairing) -> Box<dyn PairingStrategy> {
+    match pairing {
+        Pairing::LowestPick => Box::new(LowestPick),
+        Pairing::Bracket => Box::new(Bracket),
+        Pairing::Swiss => Box::new(Swiss),
+        Pairing::InformationGain => Box::new(InformationGain),
+    }
+}
+
+// A random anime with the fewest picks, against another with the fewest picks.
+// With ratings from earlier tournaments, the opponent is the one closest to it
+// in strength.
+pub struct LowestPick;
+
+impl LowestPick {
+    fn lowest_picks(round: &Round, anime: &[i32]) -> Vec<i32> {
+        let num_picks = count_picks(round.decisions, anime);
+        let lowest_pick_num = match num_picks.values().min() {
+            Some(num) => *num,
+            None => return vec![]
+        };
+        anime.iter().filter(|a| num_picks[*a] == lowest_pick_num).copied().collect()
+    }
+}
+
+impl PairingStrategy for LowestPick {
+    fn next_pair(&self, round: &Round) -> Option<(i32, i32)> {
+        let pairs = round.pairs();
+        let mut rng = round.rng();
+
+        let mut lefts: Vec<i32> = pairs.iter().flat_map(|(a, b)| vec![*a, *b]).collect();
+        lefts.sort_unstable();
+        lefts.dedup();
+        let left = *LowestPick::lowest_picks(round, &lefts).choose(&mut rng)?;
+
+        let opponents: Vec<i32> = pairs.iter()
+            .filter_map(|(a, b)| if *a == left { Some(*b) } else if *b == left { Some(*a) } else { None })
+            .collect();
+        let lowest = LowestPick::lowest_picks(round, &opponents);
+        let right = match round.ratings.get(&left) {
+            Some(rating) => lowest.iter()
+                .filter(|a| round.ratings.contains_key(*a))
+                .min_by(|a, b| (round.ratings[*a] - rating).abs().partial_cmp(&(round.ratings[*b] - rating).abs()).unwrap_or(Ordering::Equal))
+                .copied()
+                .or_else(|| lowest.choose(&mut rng).copied()),
+            None => lowest.choose(&mut rng).copied()
+        }?;
+
+        Some((left, right))
+    }
+}
+
+// A classic bracket: the anime play round by round in the tournament's order,
+// first against second, third against fourth, and so on. With an odd number,
+// the last one waits for the next round.
+pub struct Bracket;
+
+impl PairingStrategy for Bracket {
+    fn next_pair(&self, round: &Round) -> Option<(i32, i32)> {
+        let num_picks = count_picks(round.decisions, round.anime);
+        let position = |a: &i32| round.anime.iter().position(|b| b == a);
+
+        let mut order = round.remaining.to_vec();
+        order.sort_by_key(|a| (num_picks[a], position(a)));
+        round.first_pair(&order)
+    }
+}
+
+// Swiss: the anime with the most picks so far meets the next best one it hasn't
+// met yet.
+pub struct Swiss;
+
+impl PairingStrategy for Swiss {
+    fn next_pair(&self, round: &Round) -> Option<(i32, i32)> {
+        let num_picks = count_picks(round.decisions, round.anime);
+        let position = |a: &i32| round.anime.iter().position(|b| b == a);
+
+        let mut order = round.remaining.to_vec();
+        order.sort_by_key(|a| (-num_picks[a], position(a)));
+        round.first_pair(&order)
+    }
+}
+
+// Ask the pair we know least about: fit Bradley-Terry strengths to the decisions
+// so far and take the pair whose outcome is closest to a coin toss, weighted by
+// how unsure both strengths still are.
+pub struct InformationGain;
+
+impl PairingStrategy for InformationGain {
+    fn next_pair(&self, round: &Round) -> Option<(i32, i32)> {
+        let fit = bradley_terry::fit_decisions(round.anime, round.decisions);
+        let std_error = |a: i32| fit.anime.iter().position(|b| *b == a).map(|i| fit.std_error[i]).unwrap_or(0.0);
+
+        let gain = |(a, b): &(i32, i32)| -> f64 {
+            // a sure thing has no entropy, but p ln p is NaN at 0 and 1
+            let p = fit.probability(*a, *b).unwrap_or(0.5).clamp(f64::EPSILON, 1.0 - f64::EPSILON);
+            let entropy = -(p * p.ln() + (1.0 - p) * (1.0 - p).ln());
+            entropy * (std_error(*a).powi(2) + std_error(*b).powi(2)).sqrt()
+        };
+
+        round.pairs().into_iter()
+            .max_by(|x, y| gain(x).partial_cmp(&gain(y)).unwrap_or(Ordering::Equal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::decided;
+
+    fn round<'a>(anime: &'a [i32], remaining: &'a [i32], decisions: &'a [Decision], ratings: &'a HashMap<i32, f64>) -> Round<'a> {
+        Round { anime, remaining, decisions, ratings, seed: 7 }
+    }
+
+    #[test]
+    fn pairs_that_have_met_wait_until_every_pair_left_has_drawn() {
+        let ratings = HashMap::new();
+        let decisions = vec![decided(1, 2, Pick::Undecided)];
+        assert_eq!(round(&[1, 2, 3], &[1, 2, 3], &decisions, &ratings).pairs(), vec![(1, 3), (2, 3)]);
+        assert_eq!(round(&[1, 2], &[1, 2], &decisions, &ratings).pairs(), vec![(1, 2)]);
+    }
+
+    #[test]
+    fn lowest_pick_pairs_the_anime_with_the_fewest_picks() {
+        let ratings = HashMap::new();
+        let decisions = vec![decided(1, 2, Pick::Left), decided(3, 4, Pick::Undecided)];
+        // 3 and 4 have already drawn, so one of them meets 1
+        let (a, b) = LowestPick.next_pair(&round(&[1, 2, 3, 4], &[1, 3, 4], &decisions, &ratings)).unwrap();
+        assert!((a == 3 || a == 4) && b == 1);
+    }
+
+    #[test]
+    fn lowest_pick_meets_the_closest_rating() {
+        let ratings: HashMap<i32, f64> = vec![(1, 1500.0), (2, 1900.0), (3, 1520.0)].into_iter().collect();
+        let (a, b) = LowestPick.next_pair(&round(&[1, 2, 3], &[1, 2, 3], &[], &ratings)).unwrap();
+        let expected = match a {
+            1 => 3,
+            2 => 3,
+            _ => 1
+        };
+        assert_eq!(b, expected);
+    }
+
+    #[test]
+    fn bracket_plays_first_against_second() {
+        let ratings = HashMap::new();
+        assert_eq!(Bracket.next_pair(&round(&[4, 3, 2, 1], &[4, 3, 2, 1], &[], &ratings)), Some((4, 3)));
+
+        let decisions = vec![decided(4, 3, Pick::Left)];
+        assert_eq!(Bracket.next_pair(&round(&[4, 3, 2, 1], &[4, 2, 1], &decisions, &ratings)), Some((2, 1)));
+    }
+
+    #[test]
+    fn swiss_pairs_the_most_picked_first() {
+        let ratings = HashMap::new();
+        let decisions = vec![decided(1, 2, Pick::Left), decided(3, 4, Pick::Left)];
+        assert_eq!(Swiss.next_pair(&round(&[1, 2, 3, 4, 5], &[1, 3, 5], &decisions, &ratings)), Some((1, 3)));
+    }
+
+    #[test]
+    fn information_gain_asks_about_the_least_known_pair() {
+        let ratings = HashMap::new();
+        // 1 has beaten 2 three times over, so 3 against either is less certain
+        let decisions = vec![decided(1, 2, Pick::Left), decided(1, 2, Pick::Left), decided(1, 2, Pick::Left)];
+        let pair = InformationGain.next_pair(&round(&[1, 2, 3], &[1, 2, 3], &decisions, &ratings)).unwrap();
+        assert!(pair.0 == 3 || pair.1 == 3);
+    }
+
+    #[test]
+    fn the_same_seed_pairs_the_same_way() {
+        let ratings = HashMap::new();
+        let anime: Vec<i32> = (1..=20).collect();
+        let pair = |seed: u64| LowestPick.next_pair(&Round { anime: &anime, remaining: &anime, decisions: &[], ratings: &ratings, seed });
+        assert_eq!(pair(42), pair(42));
+        assert!((0..10).any(|seed| pair(seed) != pair(42)));
+    }
+
+    #[test]
+    fn information_gain_still_picks_a_pair_when_the_results_are_lopsided() {
+        let ratings = HashMap::new();
+        let decisions: Vec<Decision> = (0..200).map(|_| decided(1, 2, Pick::Left)).collect();
+        assert_eq!(InformationGain.next_pair(&round(&[1, 2], &[1, 2], &decisions, &ratings)), Some((1, 2)));
+    }
+}
@@ -0,0 +1,98 @@
+use std::cmp::Ordering;
+
+use super::super::models::*;
+use super::compare;
+
+// Binary insertion sort, where every comparison is a decision.
+//
+// The sort is replayed from the start each time using the decisions made so far,
+// so it returns the anime placed so far (best first) and the next pair it needs
+// decided, or None once the order is complete. A draw places the anime right
+// below the one it drew with.
+pub fn insertion_sort(anime: &[i32], decisions: &[Decision]) -> (Vec<i32>, Option<(i32, i32)>) {
+    let mut sorted: Vec<i32> = Vec::with_capacity(anime.len());
+
+    for a in anime {
+        let (mut low, mut high) = (0, sorted.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            match compare(decisions, *a, sorted[mid]) {
+                Some(Ordering::Greater) => high = mid,
+                Some(Ordering::Less) => low = mid + 1,
+                Some(Ordering::Equal) => {
+                    low = mid + 1;
+                    break;
+                },
+                None => {
+                    let pair = (*a, sorted[mid]);
+                    return (sorted, Some(pair));
+                }
+            }
+        }
+        sorted.insert(low, *a);
+    }
+
+    (sorted, None)
+}
+
+pub fn next_pair(anime: &[i32], decisions: &[Decision]) -> Option<(i32, i32)> {
+    insertion_sort(anime, decisions).1
+}
+
+pub fn ranking(anime: &[i32], decisions: &[Decision]) -> Vec<i32> {
+    insertion_sort(anime, decisions).0
+}
+
+pub fn winner(anime: &[i32], decisions: &[Decision]) -> Option<i32> {
+    match insertion_sort(anime, decisions) {
+        (sorted, None) => sorted.first().copied(),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::decided;
+
+    // answer each pair the sort asks for with the better anime, the one listed first in `best`
+    fn sort_by(anime: &[i32], best: &[i32]) -> Vec<Decision> {
+        let place = |a: i32| best.iter().position(|b| *b == a);
+        let mut decisions = vec![];
+        while let Some((a, b)) = next_pair(anime, &decisions) {
+            let pick = if place(a) < place(b) { Pick::Left } else { Pick::Right };
+            decisions.push(decided(a, b, pick));
+        }
+        decisions
+    }
+
+    #[test]
+    fn sorts_by_the_answers() {
+        let anime = [1, 2, 3, 4, 5, 6, 7];
+        let best = [5, 2, 7, 1, 6, 3, 4];
+        let decisions = sort_by(&anime, &best);
+        assert_eq!(ranking(&anime, &decisions), best.to_vec());
+        assert_eq!(winner(&anime, &decisions), Some(5));
+    }
+
+    #[test]
+    fn asks_no_more_than_a_binary_insertion_sort_needs() {
+        let anime = [1, 2, 3, 4, 5, 6, 7, 8];
+        let decisions = sort_by(&anime, &[8, 7, 6, 5, 4, 3, 2, 1]);
+        // ceil(log2(i)) for i in 1..=8
+        assert!(decisions.len() <= 17);
+    }
+
+    #[test]
+    fn a_draw_places_the_anime_below() {
+        let decisions = vec![decided(2, 1, Pick::Undecided)];
+        assert_eq!(insertion_sort(&[1, 2], &decisions), (vec![1, 2], None));
+    }
+
+    #[test]
+    fn no_winner_until_sorted() {
+        let decisions = vec![decided(2, 1, Pick::Left)];
+        assert_eq!(winner(&[1, 2, 3], &decisions), None);
+        assert!(next_pair(&[1, 2, 3], &decisions).is_some());
+    }
+}
@@ -0,0 +1,173 @@
+// Round robin: every anime meets every other one exactly once. It takes the
+// most questions, but nothing is left to the luck of the pairings, so it suits
+// a short list.
+//
+// The pairs are asked round by round, with each anime in each round once, so
+// no anime waits long for its next pair or comes up many times in a row.
+
+use std::cmp::Ordering;
+
+use super::super::models::*;
+use super::{compare, find_decision, Standing};
+
+// what breaks a tie on score, in order
+pub const TIEBREAKS: [&str; 3] = ["H2H", "S-B", "Wins"];
+
+// The circle method: keep the first anime in place and turn the rest round
+// one place each round. With an odd number one anime sits out each round.
+pub fn schedule(anime: &[i32]) -> Vec<(i32, i32)> {
+    let mut circle: Vec<Option<i32>> = anime.iter().map(|a| Some(*a)).collect();
+    if circle.len() % 2 == 1 {
+        circle.push(None);
+    }
+    let n = circle.len();
+
+    let mut pairs = vec![];
+    for round in 0..n.saturating_sub(1) {
+        for i in 0..n / 2 {
+            if let (Some(a), Some(b)) = (circle[i], circle[n - 1 - i]) {
+                // the first anime swaps sides every other round
+                pairs.push(if i == 0 && round % 2 == 1 { (b, a) } else { (a, b) });
+            }
+        }
+        circle[1..].rotate_right(1);
+    }
+    pairs
+}
+
+pub fn next_pair(anime: &[i32], decisions: &[Decision]) -> Option<(i32, i32)> {
+    schedule(anime).into_iter()
+        .find(|(a, b)| find_decision(decisions, *a, *b).is_none())
+}
+
+// how `a` did against `b`: 1 for a win, a half for a draw
+fn score(decisions: &[Decision], a: i32, b: i32) -> Option<f64> {
+    match compare(decisions, a, b)? {
+        Ordering::Greater => Some(1.0),
+        Ordering::Equal => Some(0.5),
+        Ordering::Less => Some(0.0),
+    }
+}
+
+// Best first: by score, then the score against the anime on the same score,
+// then Sonneborn-Berger (the total score of the anime it beat, and half that of
+// those it drew with), then the number of wins, then the order of `anime`.
+pub fn standings(anime: &[i32], decisions: &[Decision]) -> Vec<Standing> {
+    let results = |a: i32| -> Vec<(i32, f64)> {
+        anime.iter()
+            .filter(|b| **b != a)
+            .filter_map(|b| score(decisions, a, *b).map(|score| (*b, score)))
+            .collect()
+    };
+    let total = |a: i32| -> f64 { results(a).iter().map(|(_, score)| score).sum() };
+
+    let mut standings: Vec<Standing> = anime.iter().map(|a| {
+        let results = results(*a);
+        // folded from 0 rather than summed, as an empty sum of floats is -0
+        let head_to_head = results.iter()
+            .filter(|(b, _)| total(*b) == total(*a))
+            .fold(0.0, |sum, (_, score)| sum + score);
+        let sonneborn_berger = results.iter().fold(0.0, |sum, (b, score)| sum + score * total(*b));
+        let wins = results.iter().filter(|(_, score)| *score == 1.0).count() as f64;
+        Standing::new(*a, &results, 0, vec![head_to_head, sonneborn_berger, wins])
+    }).collect();
+
+    Standing::sort(&mut standings);
+    standings
+}
+
+pub fn winner(anime: &[i32], decisions: &[Decision]) -> Option<i32> {
+    if next_pair(anime, decisions).is_some() {
+        return None;
+    }
+    ranking(anime, decisions).first().copied()
+}
+
+// best first, as the standings are so far
+pub fn ranking(anime: &[i32], decisions: &[Decision]) -> Vec<i32> {
+    standings(anime, decisions).iter().map(|s| s.anime).collect()
+}
+
+// how each anime did against each other one
+pub struct HeadToHead {
+    pub anime: Vec<i32>,
+    // results[i][j] is how anime[i] did against anime[j], if they've met
+    pub results: Vec<Vec<Option<Ordering>>>,
+}
+
+impl HeadToHead {
+    pub fn new(anime: &[i32], decisions: &[Decision]) -> HeadToHead {
+        HeadToHead {
+            anime: anime.to_vec(),
+            results: anime.iter()
+                .map(|a| anime.iter().map(|b| if a == b { None } else { compare(decisions, *a, *b) }).collect())
+                .collect(),
+        }
+    }
+
+    pub fn result(&self, a: i32, b: i32) -> Option<Ordering> {
+        let i = self.anime.iter().position(|anime_id| *anime_id == a)?;
+        let j = self.anime.iter().position(|anime_id| *anime_id == b)?;
+        self.results[i][j]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::decided;
+
+    #[test]
+    fn every_pair_is_asked_once() {
+        for n in 2..=9 {
+            let anime: Vec<i32> = (1..=n).collect();
+            let mut pairs: Vec<(i32, i32)> = schedule(&anime).into_iter().map(|(a, b)| (a.min(b), a.max(b))).collect();
+            pairs.sort();
+            pairs.dedup();
+            assert_eq!(pairs.len(), (n * (n - 1) / 2) as usize, "{} anime", n);
+        }
+    }
+
+    #[test]
+    fn each_anime_is_in_each_round_once() {
+        let anime: Vec<i32> = (1..=6).collect();
+        for round in schedule(&anime).chunks(3) {
+            let mut in_round: Vec<i32> = round.iter().flat_map(|(a, b)| vec![*a, *b]).collect();
+            in_round.sort();
+            assert_eq!(in_round, anime);
+        }
+    }
+
+    #[test]
+    fn the_winner_is_known_once_every_pair_is() {
+        let anime = [1, 2, 3];
+        let mut decisions = vec![decided(1, 2, Pick::Left), decided(2, 3, Pick::Left)];
+        assert_eq!(next_pair(&anime, &decisions).map(|(a, b)| (a.min(b), a.max(b))), Some((1, 3)));
+        assert_eq!(winner(&anime, &decisions), None);
+
+        decisions.push(decided(1, 3, Pick::Undecided));
+        assert_eq!(next_pair(&anime, &decisions), None);
+        assert_eq!(winner(&anime, &decisions), Some(1));
+        let standings = standings(&anime, &decisions);
+        assert_eq!((standings[0].score, standings[0].wins, standings[0].draws), (1.5, 1, 1));
+    }
+
+    #[test]
+    fn a_tie_on_score_goes_to_the_one_that_won_between_them() {
+        // 1 beats 2, 2 beats 3 and 4, 3 beats 1 and 4, 4 beats 1, so 2 and 3
+        // are both on 2
+        let anime = [1, 2, 3, 4];
+        let decisions = [
+            decided(1, 2, Pick::Left),
+            decided(1, 3, Pick::Right),
+            decided(1, 4, Pick::Right),
+            decided(2, 3, Pick::Left),
+            decided(2, 4, Pick::Left),
+            decided(3, 4, Pick::Left),
+        ];
+        assert_eq!(ranking(&anime, &decisions)[..2], [2, 3]);
+        let head_to_head = HeadToHead::new(&anime, &decisions);
+        assert_eq!(head_to_head.result(3, 2), Some(Ordering::Less));
+        assert_eq!(head_to_head.result(1, 1), None);
+    }
+}
@@ -0,0 +1,271 @@
+// Swiss: a fixed number of rounds, each pairing anime on the same score that
+// haven't met yet. A long list gets sorted out in far fewer questions than a
+// round robin, and without one bad pairing knocking a good anime out early.
+//
+// The rounds aren't saved: each one is paired again from the results of the
+// rounds before it, so the decisions are all a tournament needs.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::super::models::*;
+use super::{compare, find_decision, Standing};
+
+// a round's pairs, and the anime sitting it out when there's an odd number
+pub struct SwissRound {
+    pub pairs: Vec<(i32, i32)>,
+    pub bye: Option<i32>,
+}
+
+// what breaks a tie on score, in order
+pub const TIEBREAKS: [&str; 2] = ["Buchholz", "S-B"];
+
+// enough rounds for one anime to win them all only if it's the best
+pub fn default_rounds(num_anime: usize) -> usize {
+    num_anime.next_power_of_two().trailing_zeros() as usize
+}
+
+// Best first: by score, then Buchholz (the total score of the anime it met),
+// then Sonneborn-Berger (the total score of the anime it beat, and half that of
+// those it drew with), then the order of `anime`. Only the results of `rounds`
+// count.
+pub fn standings(anime: &[i32], decisions: &[Decision], rounds: &[SwissRound]) -> Vec<Standing> {
+    // each anime's opponents and how it did against them
+    let mut results: HashMap<i32, Vec<(i32, f64)>> = HashMap::new();
+    let mut byes: HashMap<i32, i32> = HashMap::new();
+    for round in rounds {
+        for (a, b) in &round.pairs {
+            let score = match compare(decisions, *a, *b) {
+                Some(Ordering::Greater) => 1.0,
+                Some(Ordering::Equal) => 0.5,
+                Some(Ordering::Less) => 0.0,
+                None => continue
+            };
+            results.entry(*a).or_default().push((*b, score));
+            results.entry(*b).or_default().push((*a, 1.0 - score));
+        }
+        if let Some(bye) = round.bye {
+            *byes.entry(bye).or_default() += 1;
+        }
+    }
+
+    let no_results = vec![];
+    let results_of = |a: &i32| results.get(a).unwrap_or(&no_results);
+    let score = |a: &i32| -> f64 {
+        results_of(a).iter().map(|(_, score)| score).sum::<f64>() + *byes.get(a).unwrap_or(&0) as f64
+    };
+
+    let mut standings: Vec<Standing> = anime.iter().map(|a| {
+        let results = results_of(a);
+        // folded from 0 rather than summed, as an empty sum of floats is -0
+        let buchholz = results.iter().fold(0.0, |total, (opponent, _)| total + score(opponent));
+        let sonneborn_berger = results.iter().fold(0.0, |total, (opponent, result)| total + result * score(opponent));
+        Standing::new(*a, results, *byes.get(a).unwrap_or(&0), vec![buchholz, sonneborn_berger])
+    }).collect();
+
+    Standing::sort(&mut standings);
+    standings
+}
+
+// how many pairings to try before settling for a greedy one, as trying them
+// all takes exponentially long once most of the anime have met
+const MAX_STEPS: usize = 10_000;
+
+fn have_met(met: &[(i32, i32)], a: i32, b: i32) -> bool {
+    met.contains(&(a, b)) || met.contains(&(b, a))
+}
+
+// Pair the anime in `order` so none meets one it has met before. With an odd
+// number, the lowest placed one that hasn't had a bye yet sits out.
+fn pair_round(order: &[i32], met: &[(i32, i32)], byes: &[i32]) -> Option<SwissRound> {
+    let mut steps = 0;
+    let mut round = sit_out(order, byes, |rest| pair_up(rest, met, &mut steps));
+    if round.is_none() && steps > MAX_STEPS {
+        round = sit_out(order, byes, |rest| pair_greedily(rest, met));
+    }
+    round
+}
+
+// the anime paired with `pair`, giving the lowest placed one that hasn't had a
+// bye yet a bye when there's an odd number
+fn sit_out(order: &[i32], byes: &[i32], mut pair: impl FnMut(&[i32]) -> Option<Vec<(i32, i32)>>) -> Option<SwissRound> {
+    if order.len() % 2 == 1 {
+        for bye in order.iter().rev().filter(|a| !byes.contains(a)) {
+            let rest: Vec<i32> = order.iter().filter(|a| *a != bye).copied().collect();
+            if let Some(pairs) = pair(&rest) {
+                return Some(SwissRound { pairs, bye: Some(*bye) });
+            }
+        }
+        return None;
+    }
+    pair(order).map(|pairs| SwissRound { pairs, bye: None })
+}
+
+// the first anime against the next one down it hasn't met, going back to try
+// the one after if that leaves the rest without a pairing, for up to MAX_STEPS
+fn pair_up(order: &[i32], met: &[(i32, i32)], steps: &mut usize) -> Option<Vec<(i32, i32)>> {
+    *steps += 1;
+    if *steps > MAX_STEPS {
+        return None;
+    }
+    let (first, rest) = match order.split_first() {
+        Some(split) => split,
+        None => return Some(vec![])
+    };
+
+    for (i, opponent) in rest.iter().enumerate() {
+        if have_met(met, *first, *opponent) {
+            continue;
+        }
+        let mut others = rest.to_vec();
+        others.remove(i);
+        if let Some(mut pairs) = pair_up(&others, met, steps) {
+            pairs.insert(0, (*first, *opponent));
+            return Some(pairs);
+        }
+    }
+    None
+}
+
+// each anime in turn against the next one down it hasn't met, without going
+// back, so it can miss a pairing there was
+fn pair_greedily(order: &[i32], met: &[(i32, i32)]) -> Option<Vec<(i32, i32)>> {
+    let mut unpaired = order.to_vec();
+    let mut pairs = vec![];
+    while !unpaired.is_empty() {
+        let first = unpaired.remove(0);
+        let i = unpaired.iter().position(|opponent| !have_met(met, first, *opponent))?;
+        pairs.push((first, unpaired.remove(i)));
+    }
+    Some(pairs)
+}
+
+// The rounds paired so far, the last of which may still be going. The rounds
+// stop early if there's no way to pair the next one without a rematch.
+pub fn play(rounds: usize, anime: &[i32], decisions: &[Decision]) -> Vec<SwissRound> {
+    let mut played: Vec<SwissRound> = vec![];
+
+    while played.len() < rounds {
+        let order: Vec<i32> = standings(anime, decisions, &played).iter().map(|s| s.anime).collect();
+        let met: Vec<(i32, i32)> = played.iter().flat_map(|round| round.pairs.iter().copied()).collect();
+        let byes: Vec<i32> = played.iter().filter_map(|round| round.bye).collect();
+
+        let round = match pair_round(&order, &met, &byes) {
+            Some(round) => round,
+            None => break
+        };
+        let finished = round.pairs.iter().all(|(a, b)| find_decision(decisions, *a, *b).is_some());
+        played.push(round);
+        if !finished {
+            break;
+        }
+    }
+    played
+}
+
+pub fn next_pair(rounds: usize, anime: &[i32], decisions: &[Decision]) -> Option<(i32, i32)> {
+    let played = play(rounds, anime, decisions);
+    let round = played.last()?;
+    round.pairs.iter()
+        .find(|(a, b)| find_decision(decisions, *a, *b).is_none())
+        .copied()
+}
+
+pub fn winner(rounds: usize, anime: &[i32], decisions: &[Decision]) -> Option<i32> {
+    if next_pair(rounds, anime, decisions).is_some() {
+        return None;
+    }
+    ranking(rounds, anime, decisions).first().copied()
+}
+
+// best first, as the standings are so far
+pub fn ranking(rounds: usize, anime: &[i32], decisions: &[Decision]) -> Vec<i32> {
+    standings(anime, decisions, &play(rounds, anime, decisions)).iter()
+        .map(|s| s.anime)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::decided;
+
+    // every pair asked, with the lower id always winning
+    fn run(rounds: usize, anime: &[i32]) -> Vec<Decision> {
+        let mut decisions = vec![];
+        while let Some((a, b)) = next_pair(rounds, anime, &decisions) {
+            decisions.push(decided(a, b, if a < b { Pick::Left } else { Pick::Right }));
+        }
+        decisions
+    }
+
+    #[test]
+    fn enough_rounds_for_one_anime_to_win_them_all() {
+        assert_eq!(default_rounds(2), 1);
+        assert_eq!(default_rounds(5), 3);
+        assert_eq!(default_rounds(8), 3);
+    }
+
+    #[test]
+    fn the_best_wins_without_a_rematch() {
+        let anime: Vec<i32> = (1..=8).collect();
+        let decisions = run(3, &anime);
+        assert_eq!(decisions.len(), 12);
+        let mut pairs: Vec<(i32, i32)> = decisions.iter()
+            .map(|d| (d.left_anime.min(d.right_anime), d.left_anime.max(d.right_anime)))
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        assert_eq!(pairs.len(), 12);
+        assert_eq!(winner(3, &anime, &decisions), Some(1));
+    }
+
+    #[test]
+    fn the_same_anime_never_sits_out_twice() {
+        let anime: Vec<i32> = (1..=5).collect();
+        let decisions = run(3, &anime);
+        let rounds = play(3, &anime, &decisions);
+        assert_eq!(rounds.len(), 3);
+        let mut byes: Vec<i32> = rounds.iter().filter_map(|round| round.bye).collect();
+        byes.sort();
+        byes.dedup();
+        assert_eq!(byes.len(), 3);
+        assert_eq!(winner(3, &anime, &decisions), Some(1));
+    }
+
+    #[test]
+    fn going_back_finds_a_pairing_the_greedy_one_misses() {
+        let met = [(1, 2), (2, 4)];
+        assert_eq!(pair_greedily(&[1, 2, 3, 4], &met), None);
+        assert_eq!(pair_up(&[1, 2, 3, 4], &met, &mut 0), Some(vec![(1, 4), (2, 3)]));
+    }
+
+    #[test]
+    fn a_long_search_falls_back_to_pairing_greedily() {
+        // 14 has met everyone but 15, so there's no pairing with 15 sitting
+        // out, and it takes more than MAX_STEPS to find that out
+        let order: Vec<i32> = (1..=15).collect();
+        let met: Vec<(i32, i32)> = (1..14).map(|a| (a, 14)).collect();
+        let round = pair_round(&order, &met, &[]).unwrap();
+        assert_eq!(round.bye, Some(14));
+        assert!(round.pairs.contains(&(13, 15)));
+    }
+
+    #[test]
+    fn ties_on_score_are_broken_by_the_opponents_scores() {
+        let rounds = [
+            SwissRound { pairs: vec![(1, 2), (3, 4)], bye: None },
+            SwissRound { pairs: vec![(1, 3), (2, 4)], bye: None },
+        ];
+        let decisions = [
+            decided(1, 2, Pick::Left),
+            decided(3, 4, Pick::Left),
+            decided(1, 3, Pick::Left),
+            decided(2, 4, Pick::Undecided),
+        ];
+        let standings = standings(&[4, 3, 2, 1], &decisions, &rounds);
+        assert_eq!(standings.iter().map(|s| s.anime).collect::<Vec<_>>(), vec![1, 3, 2, 4]);
+        assert_eq!(standings[2].tiebreaks, vec![2.5, 0.25]);
+        assert_eq!(standings[3].tiebreaks, vec![1.5, 0.25]);
+    }
+}
@@ -0,0 +1,126 @@
+use std::cmp::Ordering;
+
+use super::super::models::*;
+use super::compare;
+
+// Keep a sorted list of the best k anime seen so far. Each new anime is compared
+// with the last of the list first, since most of them won't make it in, and only
+// searched into the list if it beats that one. A draw with the last of a full
+// list keeps the one already in it; a draw further up places the new anime right
+// below the one it drew with.
+//
+// Like the ranking this is replayed from the decisions each time, returning the
+// best so far, the anime knocked out, and the next pair it needs decided.
+pub fn select(k: usize, anime: &[i32], decisions: &[Decision]) -> (Vec<i32>, Vec<i32>, Option<(i32, i32)>) {
+    let k = k.max(1);
+    let mut best: Vec<i32> = Vec::with_capacity(k + 1);
+    let mut out: Vec<i32> = vec![];
+
+    for a in anime {
+        let mut high = best.len();
+        if best.len() == k {
+            let last = best[k - 1];
+            match compare(decisions, *a, last) {
+                Some(Ordering::Greater) => high = k - 1,
+                Some(_) => {
+                    out.push(*a);
+                    continue;
+                },
+                None => return (best, out, Some((*a, last)))
+            }
+        }
+
+        let mut low = 0;
+        while low < high {
+            let mid = (low + high) / 2;
+            match compare(decisions, *a, best[mid]) {
+                Some(Ordering::Greater) => high = mid,
+                Some(Ordering::Less) => low = mid + 1,
+                Some(Ordering::Equal) => {
+                    low = mid + 1;
+                    break;
+                },
+                None => {
+                    let pair = (*a, best[mid]);
+                    return (best, out, Some(pair));
+                }
+            }
+        }
+        best.insert(low, *a);
+        if best.len() > k {
+            out.extend(best.pop());
+        }
+    }
+
+    (best, out, None)
+}
+
+pub fn remaining_anime(k: usize, anime: &[i32], decisions: &[Decision]) -> Vec<i32> {
+    let (_, out, _) = select(k, anime, decisions);
+    anime.iter().filter(|a| !out.contains(a)).copied().collect()
+}
+
+pub fn next_pair(k: usize, anime: &[i32], decisions: &[Decision]) -> Option<(i32, i32)> {
+    select(k, anime, decisions).2
+}
+
+pub fn top_k(k: usize, anime: &[i32], decisions: &[Decision]) -> Vec<i32> {
+    select(k, anime, decisions).0
+}
+
+pub fn winner(k: usize, anime: &[i32], decisions: &[Decision]) -> Option<i32> {
+    match select(k, anime, decisions) {
+        (best, _, None) => best.first().copied(),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::decided;
+
+    // answer each pair with the better anime, the one listed first in `best`
+    fn select_by(k: usize, anime: &[i32], best: &[i32]) -> Vec<Decision> {
+        let place = |a: i32| best.iter().position(|b| *b == a);
+        let mut decisions = vec![];
+        while let Some((a, b)) = next_pair(k, anime, &decisions) {
+            let pick = if place(a) < place(b) { Pick::Left } else { Pick::Right };
+            decisions.push(decided(a, b, pick));
+        }
+        decisions
+    }
+
+    #[test]
+    fn picks_the_best_k_in_order() {
+        let anime = [1, 2, 3, 4, 5, 6, 7];
+        let decisions = select_by(3, &anime, &[6, 1, 4, 7, 2, 5, 3]);
+        assert_eq!(top_k(3, &anime, &decisions), vec![6, 1, 4]);
+        assert_eq!(winner(3, &anime, &decisions), Some(6));
+
+        let mut remaining = remaining_anime(3, &anime, &decisions);
+        remaining.sort_unstable();
+        assert_eq!(remaining, vec![1, 4, 6]);
+    }
+
+    #[test]
+    fn an_anime_that_loses_to_the_last_is_out_at_once() {
+        let decisions = vec![decided(2, 1, Pick::Right), decided(3, 2, Pick::Right)];
+        let (best, out, next) = select(2, &[1, 2, 3], &decisions);
+        assert_eq!(best, vec![1, 2]);
+        assert_eq!(out, vec![3]);
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn a_draw_with_the_last_of_a_full_list_keeps_the_one_in_it() {
+        let decisions = vec![decided(2, 1, Pick::Right), decided(3, 2, Pick::Undecided)];
+        assert_eq!(top_k(2, &[1, 2, 3], &decisions), vec![1, 2]);
+    }
+
+    #[test]
+    fn k_of_zero_is_taken_as_one() {
+        let decisions = select_by(0, &[1, 2, 3], &[2, 3, 1]);
+        assert_eq!(top_k(0, &[1, 2, 3], &decisions), vec![2]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use super::super::models::*;
+
+// the pair a ballot is for, whichever way round it was asked, and which time they met
+fn pair_key(ballot: &Ballot) -> (i32, i32, i32) {
+    (ballot.left_anime.min(ballot.right_anime), ballot.left_anime.max(ballot.right_anime), ballot.meeting)
+}
+
+// the ballots for each pair, in the order the pairs were first asked
+fn by_pair(ballots: &[Ballot]) -> Vec<Vec<&Ballot>> {
+    let mut index: HashMap<(i32, i32, i32), usize> = HashMap::new();
+    let mut pairs: Vec<Vec<&Ballot>> = vec![];
+
+    for ballot in ballots {
+        let i = *index.entry(pair_key(ballot)).or_insert_with(|| {
+            pairs.push(vec![]);
+            pairs.len() - 1
+        });
+        pairs[i].push(ballot);
+    }
+    pairs
+}
+
+// any time they met
+pub fn same_pair(ballot: &Ballot, a: i32, b: i32) -> bool {
+    (ballot.left_anime.min(ballot.right_anime), ballot.left_anime.max(ballot.right_anime)) == (a.min(b), a.max(b))
+}
+
+// a ballot towards the decision: the same pair, the same time they met
+pub fn same_decision(ballot: &Ballot, decision: &Decision) -> bool {
+    ballot.tournament == decision.tournament
+        && ballot.meeting == decision.meeting
+        && same_pair(ballot, decision.left_anime, decision.right_anime)
+}
+
+fn room_ballot<'a>(pair: &[&'a Ballot]) -> Option<&'a Ballot> {
+    pair.iter().find(|ballot| ballot.voter == ROOM_VOTER).copied()
+}
+
+fn all_voted(pair: &[&Ballot], voters: &[i32]) -> bool {
+    room_ballot(pair).is_some() || voters.iter().all(|voter| pair.iter().any(|ballot| ballot.voter == *voter))
+}
+
+// Put the ballots together into one decision per pair, by majority.
+//
+// A pair only counts once every voter has answered it, or a room has for all
+// of them; a tied vote, like a ballot that can't choose, is a draw.
+pub fn tally(ballots: &[Ballot], voters: &[i32]) -> Vec<Decision> {
+    by_pair(ballots).into_iter()
+        .filter(|pair| all_voted(pair, voters))
+        .map(|pair| {
+            let first = pair[0];
+            // the room's decision stands over any votes cast outside it
+            let counted = match room_ballot(&pair) {
+                Some(room) => vec![room],
+                None => pair.clone()
+            };
+            let mut left_votes = 0;
+            let mut right_votes = 0;
+            for ballot in &counted {
+                // ballots asked the other way round count for the other side
+                let picked_left = match (Pick::to_pick(ballot.pick), ballot.left_anime == first.left_anime) {
+                    (Pick::Undecided, _) => continue,
+                    (Pick::Left, same_way) => same_way,
+                    (Pick::Right, same_way) => !same_way,
+                };
+                if picked_left {
+                    left_votes += 1;
+                } else {
+                    right_votes += 1;
+                }
+            }
+
+            Decision {
+                tournament: first.tournament,
+                left_anime: first.left_anime,
+                right_anime: first.right_anime,
+                pick: if left_votes == right_votes { None } else { Some(left_votes > right_votes) },
+                meeting: first.meeting,
+            }
+        })
+        .collect()
+}
+
+// a pair that some of the voters have answered but not all of them yet, and
+// which time they're meeting
+pub fn open_pair(ballots: &[Ballot], voters: &[i32]) -> Option<(i32, i32, i32)> {
+    by_pair(ballots).into_iter()
+        .find(|pair| !all_voted(pair, voters))
+        .map(|pair| (pair[0].left_anime, pair[0].right_anime, pair[0].meeting))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ballot(voter: i32, left_anime: i32, right_anime: i32, pick: Pick) -> Ballot {
+        Ballot { tournament: 1, left_anime, right_anime, pick: Pick::to_opt(pick), voter, number: 0, meeting: 0 }
+    }
+
+    #[test]
+    fn a_pair_counts_once_every_voter_has_answered() {
+        let mut ballots = vec![ballot(1, 1, 2, Pick::Left)];
+        assert!(tally(&ballots, &[1, 2]).is_empty());
+        assert_eq!(open_pair(&ballots, &[1, 2]), Some((1, 2, 0)));
+
+        ballots.push(ballot(2, 1, 2, Pick::Left));
+        let decisions = tally(&ballots, &[1, 2]);
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].pick, Some(true));
+        assert_eq!(open_pair(&ballots, &[1, 2]), None);
+    }
+
+    #[test]
+    fn the_majority_decides() {
+        let ballots = vec![
+            ballot(1, 1, 2, Pick::Right),
+            ballot(2, 1, 2, Pick::Right),
+            ballot(3, 1, 2, Pick::Left),
+        ];
+        assert_eq!(tally(&ballots, &[1, 2, 3])[0].pick, Some(false));
+    }
+
+    #[test]
+    fn a_tie_is_a_draw_and_abstentions_dont_count() {
+        let ballots = vec![
+            ballot(1, 1, 2, Pick::Right),
+            ballot(2, 1, 2, Pick::Left),
+            ballot(3, 1, 2, Pick::Undecided),
+        ];
+        assert_eq!(tally(&ballots, &[1, 2, 3])[0].pick, None);
+    }
+
+    #[test]
+    fn a_ballot_the_other_way_round_counts_for_the_same_anime() {
+        let ballots = vec![
+            ballot(1, 1, 2, Pick::Left),
+            ballot(2, 2, 1, Pick::Right),
+            ballot(3, 2, 1, Pick::Left),
+        ];
+        let decision = &tally(&ballots, &[1, 2, 3])[0];
+        assert_eq!((decision.left_anime, decision.right_anime), (1, 2));
+        assert_eq!(decision.pick, Some(true));
+    }
+
+    #[test]
+    fn pairs_come_in_the_order_they_were_first_asked() {
+        let ballots = vec![ballot(1, 3, 4, Pick::Left), ballot(1, 1, 2, Pick::Left)];
+        let decisions = tally(&ballots, &[1]);
+        assert_eq!((decisions[0].left_anime, decisions[1].left_anime), (3, 1));
+    }
+
+    #[test]
+    fn a_rooms_decision_stands_for_everyone() {
+        let ballots = vec![
+            ballot(1, 1, 2, Pick::Left),
+            ballot(ROOM_VOTER, 1, 2, Pick::Right),
+        ];
+        let decisions = tally(&ballots, &[1, 2, 3]);
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].pick, Some(false));
+        assert_eq!(open_pair(&ballots, &[1, 2, 3]), None);
+    }
+}
@@ -0,0 +1,60 @@
+use std::error::Error;
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, TournamentError>;
+
+#[derive(Debug)]
+pub enum TournamentError {
+    Connection(String),
+    Query(diesel::result::Error),
+    NotFound { kind: &'static str, key: String },
+    // a request that can't be carried out, such as seeding a tournament that
+    // isn't a bracket
+    Invalid(String),
+    InvalidDecision(String),
+    AlreadyFinished(i32),
+}
+
+impl TournamentError {
+    pub fn not_found(kind: &'static str, id: i32) -> TournamentError {
+        TournamentError::NotFound { kind, key: format!("#{}", id) }
+    }
+
+    pub fn not_found_named(kind: &'static str, name: &str) -> TournamentError {
+        TournamentError::NotFound { kind, key: format!("'{}'", name) }
+    }
+}
+
+impl fmt::Display for TournamentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TournamentError::Connection(message) => write!(f, "Can't connect to the database: {}", message),
+            TournamentError::Query(err) => write!(f, "Database error: {}", err),
+            TournamentError::NotFound { kind, key } => write!(f, "There is no {} {}", kind, key),
+            TournamentError::Invalid(message) => write!(f, "Can't do that: {}", message),
+            TournamentError::InvalidDecision(message) => write!(f, "Invalid decision: {}", message),
+            TournamentError::AlreadyFinished(id) => write!(f, "Tournament #{} is already finished", id),
+        }
+    }
+}
+
+impl Error for TournamentError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TournamentError::Query(err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<diesel::result::Error> for TournamentError {
+    fn from(err: diesel::result::Error) -> TournamentError {
+        TournamentError::Query(err)
+    }
+}
+
+impl From<diesel::ConnectionError> for TournamentError {
+    fn from(err: diesel::ConnectionError) -> TournamentError {
+        TournamentError::Connection(err.to_string())
+    }
+}
@@ -0,0 +1,790 @@
+use std::cell::RefCell;
+
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+use super::models::*;
+use super::{engine, TournamentStore, TournamentError, Result};
+
+/// A store that keeps everything in memory, for running without a database.
+#[derive(Clone)]
+pub struct MemoryModel {
+    anime: RefCell<Vec<Anime>>,
+    slots: RefCell<Vec<Slot>>,
+    anime_slots: RefCell<Vec<AnimeSlot>>,
+    tournaments: RefCell<Vec<Tournament>>,
+    tournament_anime: RefCell<Vec<TournamentAnime>>,
+    members: RefCell<Vec<Member>>,
+    tournament_members: RefCell<Vec<TournamentMember>>,
+    ballots: RefCell<Vec<Ballot>>,
+    ratings: RefCell<Vec<Rating>>,
+    bracket_matches: RefCell<Vec<BracketMatch>>,
+    approval_ballots: RefCell<Vec<ApprovalBallot>>,
+    ranked_ballots: RefCell<Vec<RankedBallot>>,
+    rooms: RefCell<Vec<Room>>,
+    room_members: RefCell<Vec<RoomMember>>,
+    room_ballots: RefCell<Vec<RoomBallot>>,
+    // how everything was when each open transaction began
+    snapshots: RefCell<Vec<MemoryModel>>,
+}
+
+impl MemoryModel {
+    pub fn new() -> MemoryModel {
+        MemoryModel {
+            anime: RefCell::new(vec![]),
+            slots: RefCell::new(vec![]),
+            anime_slots: RefCell::new(vec![]),
+            tournaments: RefCell::new(vec![]),
+            tournament_anime: RefCell::new(vec![]),
+            members: RefCell::new(vec![]),
+            tournament_members: RefCell::new(vec![]),
+            ballots: RefCell::new(vec![]),
+            ratings: RefCell::new(vec![]),
+            bracket_matches: RefCell::new(vec![]),
+            approval_ballots: RefCell::new(vec![]),
+            ranked_ballots: RefCell::new(vec![]),
+            rooms: RefCell::new(vec![]),
+            room_members: RefCell::new(vec![]),
+            room_ballots: RefCell::new(vec![]),
+            snapshots: RefCell::new(vec![]),
+        }
+    }
+
+    // put everything back as it was in `before`, like a rolled back transaction
+    fn restore(&self, before: MemoryModel) {
+        let MemoryModel {
+            anime, slots, anime_slots, tournaments, tournament_anime, members, tournament_members, ballots, ratings,
+            bracket_matches, approval_ballots, ranked_ballots, rooms, room_members, room_ballots, ..
+        } = before;
+        self.anime.replace(anime.into_inner());
+        self.slots.replace(slots.into_inner());
+        self.anime_slots.replace(anime_slots.into_inner());
+        self.tournaments.replace(tournaments.into_inner());
+        self.tournament_anime.replace(tournament_anime.into_inner());
+        self.members.replace(members.into_inner());
+        self.tournament_members.replace(tournament_members.into_inner());
+        self.ballots.replace(ballots.into_inner());
+        self.ratings.replace(ratings.into_inner());
+        self.bracket_matches.replace(bracket_matches.into_inner());
+        self.approval_ballots.replace(approval_ballots.into_inner());
+        self.ranked_ballots.replace(ranked_ballots.into_inner());
+        self.rooms.replace(rooms.into_inner());
+        self.room_members.replace(room_members.into_inner());
+        self.room_ballots.replace(room_ballots.into_inner());
+    }
+
+    // the same anime, slots and member as the migrations
+    pub fn add_mock_anime(&self) -> Result<()> {
+        self.add_member("me")?;
+
+        let mut slot_ids = vec![];
+        for (position, name) in ["first", "second", "third"].iter().enumerate() {
+            let slot = self.add_slot(&NewSlot {
+                name: name.to_string(),
+                position: position as i32 + 1,
+                duration: None,
+                max_episodes: None,
+            })?;
+            slot_ids.push(slot.id);
+        }
+
+        let items = [
+            ("Aldnoah.Zero", 24, [true, false, false]),
+            ("Bloom Into You", 13, [true, true, false]),
+            ("Fairy Gone", 12, [true, false, false]),
+            ("Irozuku: The World In Colors", 13, [true, false, false]),
+            ("Bounen no Xamdou", 26, [true, true, false]),
+            ("Kabaneri of the Iron Fortress", 12, [true, false, false]),
+            ("Plastic Memories", 13, [true, false, false]),
+            ("Kaguya S2", 12, [false, false, true]),
+        ];
+
+        for (name, episodes, in_slots) in &items {
+            let anime = self.add_anime(name, Some(*episodes))?;
+            let anime_slot_ids: Vec<i32> = slot_ids.iter().zip(in_slots.iter())
+                .filter(|(_, in_slot)| **in_slot)
+                .map(|(slot_id, _)| *slot_id)
+                .collect();
+            self.set_anime_slots(anime.id, &anime_slot_ids)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for MemoryModel {
+    fn default() -> MemoryModel {
+        MemoryModel::new()
+    }
+}
+
+impl TournamentStore for MemoryModel {
+    fn begin_transaction(&self) -> Result<()> {
+        let before = self.clone();
+        self.snapshots.borrow_mut().push(before);
+        Ok(())
+    }
+
+    fn commit_transaction(&self) -> Result<()> {
+        self.snapshots.borrow_mut().pop();
+        Ok(())
+    }
+
+    fn rollback_transaction(&self) -> Result<()> {
+        let before = self.snapshots.borrow_mut().pop();
+        if let Some(before) = before {
+            self.restore(before);
+        }
+        Ok(())
+    }
+
+    fn add_anime(&self, name: &str, episodes: Option<i32>) -> Result<Anime> {
+        let mut anime = self.anime.borrow_mut();
+        let new_anime = Anime {
+            id: anime.iter().map(|a| a.id).max().unwrap_or(0) + 1,
+            name: name.to_string(),
+            episodes,
+        };
+
+        anime.push(new_anime.clone());
+        Ok(new_anime)
+    }
+
+    fn get_anime(&self) -> Result<Vec<Anime>> {
+        let mut anime = self.anime.borrow().clone();
+        anime.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(anime)
+    }
+
+    fn get_anime_by_id(&self, anime_id: i32) -> Result<Anime> {
+        self.anime.borrow().iter()
+            .find(|anime| anime.id == anime_id)
+            .cloned()
+            .ok_or_else(|| TournamentError::not_found("anime", anime_id))
+    }
+
+    fn get_anime_for_slot(&self, slot: &Slot) -> Result<Vec<Anime>> {
+        let anime_ids: Vec<i32> = self.anime_slots.borrow().iter()
+            .filter(|anime_slot| anime_slot.slot == slot.id)
+            .map(|anime_slot| anime_slot.anime)
+            .collect();
+
+        let mut anime: Vec<Anime> = self.anime.borrow().iter()
+            .filter(|anime| anime_ids.contains(&anime.id))
+            .cloned()
+            .collect();
+        anime.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(anime)
+    }
+
+    fn update_anime(&self, a: &Anime) -> Result<()> {
+        let mut anime = self.anime.borrow_mut();
+        let existing = anime.iter_mut()
+            .find(|anime| anime.id == a.id)
+            .ok_or_else(|| TournamentError::not_found("anime", a.id))?;
+        *existing = a.clone();
+        Ok(())
+    }
+
+    fn remove_anime(&self, anime_id: i32) -> Result<()> {
+        let in_tournament = self.tournament_anime.borrow().iter()
+            .find(|tuple| tuple.anime == anime_id)
+            .map(|tuple| tuple.tournament);
+        if let Some(tournament_id) = in_tournament {
+            return Err(TournamentError::Invalid(format!("anime #{} is in tournament #{}, so it can't be removed", anime_id, tournament_id)));
+        }
+
+        let mut anime = self.anime.borrow_mut();
+        let before = anime.len();
+        anime.retain(|anime| anime.id != anime_id);
+
+        if anime.len() == before {
+            return Err(TournamentError::not_found("anime", anime_id));
+        }
+
+        self.anime_slots.borrow_mut().retain(|anime_slot| anime_slot.anime != anime_id);
+        Ok(())
+    }
+
+    fn add_slot(&self, new_slot: &NewSlot) -> Result<Slot> {
+        let mut slots = self.slots.borrow_mut();
+        let slot = Slot {
+            id: slots.iter().map(|s| s.id).max().unwrap_or(0) + 1,
+            name: new_slot.name.clone(),
+            position: new_slot.position,
+            duration: new_slot.duration,
+            max_episodes: new_slot.max_episodes,
+        };
+
+        slots.push(slot.clone());
+        Ok(slot)
+    }
+
+    fn get_slots(&self) -> Result<Vec<Slot>> {
+        let mut slots = self.slots.borrow().clone();
+        slots.sort_by_key(|slot| (slot.position, slot.id));
+        Ok(slots)
+    }
+
+    fn get_slot(&self, slot_id: i32) -> Result<Slot> {
+        self.slots.borrow().iter()
+            .find(|slot| slot.id == slot_id)
+            .cloned()
+            .ok_or_else(|| TournamentError::not_found("slot", slot_id))
+    }
+
+    fn update_slot(&self, slot: &Slot) -> Result<()> {
+        let mut slots = self.slots.borrow_mut();
+        let existing = slots.iter_mut()
+            .find(|s| s.id == slot.id)
+            .ok_or_else(|| TournamentError::not_found("slot", slot.id))?;
+        *existing = slot.clone();
+        Ok(())
+    }
+
+    fn remove_slot(&self, slot_id: i32) -> Result<()> {
+        let in_tournament = self.tournaments.borrow().iter()
+            .find(|t| t.slot == slot_id)
+            .map(|t| t.id);
+        if let Some(tournament_id) = in_tournament {
+            return Err(TournamentError::Invalid(format!("slot #{} is used by tournament #{}, so it can't be removed", slot_id, tournament_id)));
+        }
+
+        let mut slots = self.slots.borrow_mut();
+        let before = slots.len();
+        slots.retain(|slot| slot.id != slot_id);
+
+        if slots.len() == before {
+            return Err(TournamentError::not_found("slot", slot_id));
+        }
+
+        self.anime_slots.borrow_mut().retain(|anime_slot| anime_slot.slot != slot_id);
+        Ok(())
+    }
+
+    fn get_anime_slots(&self, anime_id: i32) -> Result<Vec<Slot>> {
+        let slot_ids: Vec<i32> = self.anime_slots.borrow().iter()
+            .filter(|anime_slot| anime_slot.anime == anime_id)
+            .map(|anime_slot| anime_slot.slot)
+            .collect();
+
+        let mut slots: Vec<Slot> = self.slots.borrow().iter()
+            .filter(|slot| slot_ids.contains(&slot.id))
+            .cloned()
+            .collect();
+        slots.sort_by_key(|slot| (slot.position, slot.id));
+        Ok(slots)
+    }
+
+    fn set_anime_slots(&self, anime_id: i32, slot_ids: &[i32]) -> Result<()> {
+        let mut anime_slots = self.anime_slots.borrow_mut();
+        anime_slots.retain(|anime_slot| anime_slot.anime != anime_id);
+        for slot_id in slot_ids {
+            anime_slots.push(AnimeSlot { anime: anime_id, slot: *slot_id });
+        }
+        Ok(())
+    }
+
+    fn add_member(&self, name: &str) -> Result<Member> {
+        let mut members = self.members.borrow_mut();
+
+        // names are unique, as with the database's unique key
+        if members.iter().any(|m| m.name == name) {
+            let message = format!("member {} already exists", name);
+            return Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, Box::new(message)).into());
+        }
+        let member = Member {
+            id: members.iter().map(|m| m.id).max().unwrap_or(0) + 1,
+            name: name.to_string(),
+        };
+
+        members.push(member.clone());
+        Ok(member)
+    }
+
+    fn get_members(&self) -> Result<Vec<Member>> {
+        Ok(self.members.borrow().clone())
+    }
+
+    fn get_member(&self, member_id: i32) -> Result<Member> {
+        self.members.borrow().iter()
+            .find(|m| m.id == member_id)
+            .cloned()
+            .ok_or_else(|| TournamentError::not_found("member", member_id))
+    }
+
+    fn add_rating(&self, new_rating: &NewRating) -> Result<()> {
+        let mut ratings = self.ratings.borrow_mut();
+        let rating = Rating {
+            id: ratings.iter().map(|r| r.id).max().unwrap_or(0) + 1,
+            anime: new_rating.anime,
+            system: new_rating.system,
+            rating: new_rating.rating,
+            deviation: new_rating.deviation,
+            volatility: new_rating.volatility,
+            tournament: new_rating.tournament,
+            created_at: new_rating.created_at,
+            meeting: new_rating.meeting,
+        };
+
+        ratings.push(rating);
+        Ok(())
+    }
+
+    fn get_ratings(&self, system: System) -> Result<Vec<Rating>> {
+        Ok(self.ratings.borrow().iter()
+            .filter(|rating| rating.system == system)
+            .cloned()
+            .collect())
+    }
+
+    fn clear_ratings(&self) -> Result<()> {
+        self.ratings.borrow_mut().clear();
+        Ok(())
+    }
+
+    fn add_bracket_match(&self, bracket_match: &BracketMatch) -> Result<()> {
+        self.bracket_matches.borrow_mut().push(bracket_match.clone());
+        Ok(())
+    }
+
+    fn get_bracket_matches(&self, t: &Tournament) -> Result<Vec<BracketMatch>> {
+        let mut matches: Vec<BracketMatch> = self.bracket_matches.borrow().iter()
+            .filter(|m| m.tournament == t.id)
+            .cloned()
+            .collect();
+        matches.sort_by_key(|m| m.number);
+        Ok(matches)
+    }
+
+    fn remove_bracket_matches(&self, t: &Tournament) -> Result<()> {
+        self.bracket_matches.borrow_mut().retain(|m| m.tournament != t.id);
+        Ok(())
+    }
+
+    fn add_approval_ballot(&self, ballot: &ApprovalBallot) -> Result<()> {
+        self.approval_ballots.borrow_mut().push(ballot.clone());
+        Ok(())
+    }
+
+    fn get_approval_ballots(&self, t: &Tournament) -> Result<Vec<ApprovalBallot>> {
+        let mut ballots: Vec<ApprovalBallot> = self.approval_ballots.borrow().iter()
+            .filter(|ballot| ballot.tournament == t.id)
+            .cloned()
+            .collect();
+        ballots.sort_by_key(|ballot| (ballot.voter, ballot.anime));
+        Ok(ballots)
+    }
+
+    fn add_ranked_ballot(&self, ballot: &RankedBallot) -> Result<()> {
+        self.ranked_ballots.borrow_mut().push(ballot.clone());
+        Ok(())
+    }
+
+    fn get_ranked_ballots(&self, t: &Tournament) -> Result<Vec<RankedBallot>> {
+        let mut ballots: Vec<RankedBallot> = self.ranked_ballots.borrow().iter()
+            .filter(|ballot| ballot.tournament == t.id)
+            .cloned()
+            .collect();
+        ballots.sort_by_key(|ballot| (ballot.voter, ballot.position));
+        Ok(ballots)
+    }
+
+    fn add_room(&self, room: &Room) -> Result<()> {
+        self.rooms.borrow_mut().push(room.clone());
+        Ok(())
+    }
+
+    fn update_room(&self, room: &Room) -> Result<()> {
+        let mut rooms = self.rooms.borrow_mut();
+        let existing = rooms.iter_mut()
+            .find(|r| r.tournament == room.tournament)
+            .ok_or_else(|| TournamentError::not_found_named("room", &room.code))?;
+        existing.matchup = room.matchup;
+        existing.opened_at = room.opened_at;
+        Ok(())
+    }
+
+    fn get_room(&self, t: &Tournament) -> Result<Option<Room>> {
+        Ok(self.rooms.borrow().iter()
+            .find(|room| room.tournament == t.id)
+            .cloned())
+    }
+
+    fn find_room(&self, code: &str) -> Result<Room> {
+        self.rooms.borrow().iter()
+            .find(|room| room.code == code)
+            .cloned()
+            .ok_or_else(|| TournamentError::not_found_named("room", code))
+    }
+
+    fn add_room_member(&self, room_member: &RoomMember) -> Result<()> {
+        self.room_members.borrow_mut().push(room_member.clone());
+        Ok(())
+    }
+
+    fn get_room_members(&self, room: &Room) -> Result<Vec<Member>> {
+        let member_ids: Vec<i32> = self.room_members.borrow().iter()
+            .filter(|tuple| tuple.tournament == room.tournament)
+            .map(|tuple| tuple.member)
+            .collect();
+
+        Ok(self.members.borrow().iter()
+            .filter(|m| member_ids.contains(&m.id))
+            .cloned()
+            .collect())
+    }
+
+    fn add_room_ballot(&self, ballot: &RoomBallot) -> Result<()> {
+        self.room_ballots.borrow_mut().push(ballot.clone());
+        Ok(())
+    }
+
+    fn get_room_ballots(&self, room: &Room) -> Result<Vec<RoomBallot>> {
+        Ok(self.room_ballots.borrow().iter()
+            .filter(|ballot| ballot.tournament == room.tournament)
+            .cloned()
+            .collect())
+    }
+
+    fn create_tournament(&self, new_tournament: &NewTournament) -> Result<Tournament> {
+        let mut tournaments = self.tournaments.borrow_mut();
+        let tournament = Tournament {
+            id: tournaments.iter().map(|t| t.id).max().unwrap_or(0) + 1,
+            slot: new_tournament.slot,
+            title: new_tournament.title.clone(),
+            status: new_tournament.status,
+            created_at: new_tournament.created_at,
+            finished_at: None,
+            mode: new_tournament.mode,
+            k: new_tournament.k,
+            pairing: new_tournament.pairing,
+            rounds: new_tournament.rounds,
+            seed: new_tournament.seed,
+        };
+
+        tournaments.push(tournament.clone());
+        Ok(tournament)
+    }
+
+    fn update_tournament(&self, t: &Tournament) -> Result<()> {
+        let mut tournaments = self.tournaments.borrow_mut();
+        let existing = tournaments.iter_mut()
+            .find(|tournament| tournament.id == t.id)
+            .ok_or_else(|| TournamentError::not_found("tournament", t.id))?;
+        *existing = t.clone();
+        Ok(())
+    }
+
+    fn get_tournaments(&self) -> Result<Vec<Tournament>> {
+        Ok(self.tournaments.borrow().clone())
+    }
+
+    fn get_tournament(&self, tournament_id: i32) -> Result<Tournament> {
+        self.tournaments.borrow().iter()
+            .find(|t| t.id == tournament_id)
+            .cloned()
+            .ok_or_else(|| TournamentError::not_found("tournament", tournament_id))
+    }
+
+    fn get_tournament_anime(&self, t: &Tournament) -> Result<Vec<Anime>> {
+        let tuples: Vec<i32> = self.tournament_anime.borrow().iter()
+            .filter(|tuple| tuple.tournament == t.id)
+            .map(|tuple| tuple.anime)
+            .collect();
+
+        Ok(self.anime.borrow().iter()
+            .filter(|anime| tuples.contains(&anime.id))
+            .cloned()
+            .collect())
+    }
+
+    fn add_anime_to_tournament(&self, t: &Tournament, a: &Anime) -> Result<()> {
+        self.tournament_anime.borrow_mut().push(TournamentAnime {
+            tournament: t.id,
+            anime: a.id
+        });
+        Ok(())
+    }
+
+    fn get_tournament_members(&self, t: &Tournament) -> Result<Vec<Member>> {
+        let member_ids: Vec<i32> = self.tournament_members.borrow().iter()
+            .filter(|tuple| tuple.tournament == t.id)
+            .map(|tuple| tuple.member)
+            .collect();
+
+        Ok(self.members.borrow().iter()
+            .filter(|m| member_ids.contains(&m.id))
+            .cloned()
+            .collect())
+    }
+
+    fn add_member_to_tournament(&self, t: &Tournament, m: &Member) -> Result<()> {
+        self.tournament_members.borrow_mut().push(TournamentMember {
+            tournament: t.id,
+            member: m.id
+        });
+        Ok(())
+    }
+
+    fn get_tournament_ballots(&self, t: &Tournament) -> Result<Vec<Ballot>> {
+        let mut ballots: Vec<Ballot> = self.ballots.borrow().iter()
+            .filter(|ballot| ballot.tournament == t.id)
+            .cloned()
+            .collect();
+        ballots.sort_by_key(|ballot| ballot.number);
+        Ok(ballots)
+    }
+
+    fn insert_ballot(&self, ballot: &Ballot) -> Result<()> {
+        let mut ballots = self.ballots.borrow_mut();
+
+        // each voter answers a pair once, as with the database's primary key
+        let exists = ballots.iter().any(|b| b.tournament == ballot.tournament
+            && b.left_anime == ballot.left_anime
+            && b.right_anime == ballot.right_anime
+            && b.voter == ballot.voter
+            && b.meeting == ballot.meeting);
+        if exists {
+            return Err(TournamentError::InvalidDecision(format!("member #{} has already voted on anime #{} and #{}", ballot.voter, ballot.left_anime, ballot.right_anime)));
+        }
+
+        ballots.push(ballot.clone());
+        Ok(())
+    }
+
+    fn remove_ballots(&self, decision: &Decision) -> Result<()> {
+        self.ballots.borrow_mut().retain(|ballot| !engine::votes::same_decision(ballot, decision));
+        Ok(())
+    }
+
+    fn remove_ballot(&self, ballot: &Ballot) -> Result<()> {
+        self.ballots.borrow_mut().retain(|b| (b.tournament, b.left_anime, b.right_anime, b.voter, b.meeting)
+            != (ballot.tournament, ballot.left_anime, ballot.right_anime, ballot.voter, ballot.meeting));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_model() -> MemoryModel {
+        let model = MemoryModel::new();
+        model.add_mock_anime().unwrap();
+        model
+    }
+
+    fn new_tournament(model: &MemoryModel, format: Format) -> Tournament {
+        let slot = model.find_slot("first").unwrap();
+        let voters = model.get_members().unwrap();
+        model.add_tournament(&slot, None, format, &voters, &[]).unwrap()
+    }
+
+    // answer every pair the tournament asks as "me", returning the pairs as
+    // they were asked
+    fn answer_all(model: &MemoryModel, t: &Tournament, pick: impl Fn(&Decision) -> Pick) -> Vec<Decision> {
+        let me = model.find_member("me").unwrap();
+        let mut asked = vec![];
+        while let Some(decision) = model.next_tournament_decision(t).unwrap() {
+            let picked = Decision { pick: Pick::to_opt(pick(&decision)), ..decision.clone() };
+            model.save_tournament_decision(t, &me, picked).unwrap();
+            asked.push(decision);
+        }
+        asked
+    }
+
+    #[test]
+    fn a_tournament_has_the_slots_anime_and_voters() {
+        let model = mock_model();
+        let t = new_tournament(&model, Format::default());
+        assert_eq!(model.get_tournament_anime(&t).unwrap().len(), 7);
+        assert_eq!(model.get_tournament_members(&t).unwrap().len(), 1);
+        assert_eq!(t.status, Status::Open);
+    }
+
+    #[test]
+    fn an_elimination_runs_to_a_winner() {
+        let model = mock_model();
+        let t = new_tournament(&model, Format::default());
+
+        // the left anime always wins
+        assert_eq!(answer_all(&model, &t, |_| Pick::Left).len(), 6);
+        let t = model.get_tournament(t.id).unwrap();
+        assert_eq!(t.status, Status::Finished);
+        assert!(model.get_tournament_winner(&t).unwrap().is_some());
+        assert_eq!(model.get_ratings(System::Elo).unwrap().len(), 12);
+    }
+
+    #[test]
+    fn every_voter_answers_before_the_pair_is_decided() {
+        let model = mock_model();
+        let bo = model.add_member("bo").unwrap();
+        let t = new_tournament(&model, Format::default());
+        let me = model.find_member("me").unwrap();
+
+        let decision = model.next_tournament_decision(&t).unwrap().unwrap();
+        model.save_tournament_decision(&t, &me, Decision { pick: Pick::to_opt(Pick::Left), ..decision.clone() }).unwrap();
+        let waiting: Vec<i32> = model.waiting_voters(&t).unwrap().iter().map(|m| m.id).collect();
+        assert_eq!(waiting, vec![bo.id]);
+        assert!(model.get_tournament_decisions(&t).unwrap().is_empty());
+
+        // a second ballot from the same voter is refused, and not saved
+        assert!(model.save_tournament_decision(&t, &me, decision.clone()).is_err());
+        assert_eq!(model.get_tournament_ballots(&t).unwrap().len(), 1);
+
+        model.save_tournament_decision(&t, &bo, Decision { pick: Pick::to_opt(Pick::Right), ..decision }).unwrap();
+        let decisions = model.get_tournament_decisions(&t).unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].pick, None);
+    }
+
+    #[test]
+    fn rebuilding_the_ratings_gives_the_same_ratings() {
+        let model = mock_model();
+        let t = new_tournament(&model, Format::default());
+        answer_all(&model, &t, |_| Pick::Right);
+
+        let strengths = |system: System| -> Vec<(i32, f64)> {
+            model.get_ratings(system).unwrap().iter().map(|rating| (rating.anime, rating.rating)).collect()
+        };
+        let before = (strengths(System::Elo), strengths(System::Glicko2));
+        model.rebuild_ratings().unwrap();
+        assert_eq!((strengths(System::Elo), strengths(System::Glicko2)), before);
+    }
+
+    #[test]
+    fn a_rematch_after_a_draw_replaces_the_draw() {
+        let model = mock_model();
+        let t = new_tournament(&model, Format { mode: Mode::SingleElimination, ..Format::default() });
+        let me = model.find_member("me").unwrap();
+
+        let drawn = model.next_tournament_decision(&t).unwrap().unwrap();
+        model.save_tournament_decision(&t, &me, drawn.clone()).unwrap();
+        let rematch = model.next_tournament_decision(&t).unwrap().unwrap();
+        assert_eq!((rematch.left_anime, rematch.right_anime), (drawn.left_anime, drawn.right_anime));
+
+        model.save_tournament_decision(&t, &me, Decision { pick: Pick::to_opt(Pick::Left), ..rematch }).unwrap();
+        let ballots = model.get_tournament_ballots(&t).unwrap();
+        assert_eq!(ballots.len(), 1);
+        assert_eq!(ballots[0].pick, Some(true));
+    }
+
+    #[test]
+    fn a_seeded_tournament_replays_question_for_question() {
+        let model = mock_model();
+        let t = new_tournament(&model, Format { seed: Some(5), ..Format::default() });
+        let asked: Vec<(i32, i32)> = answer_all(&model, &t, |_| Pick::Right).iter()
+            .map(|decision| (decision.left_anime, decision.right_anime))
+            .collect();
+
+        let replayed: Vec<(i32, i32)> = model.replay_tournament(&t).unwrap().iter()
+            .map(|decision| (decision.left_anime, decision.right_anime))
+            .collect();
+        assert_eq!(replayed, asked);
+    }
+
+    #[test]
+    fn an_undo_takes_back_the_ballots_ratings() {
+        let model = mock_model();
+        let t = new_tournament(&model, Format::default());
+        let me = model.find_member("me").unwrap();
+        let decision = model.next_tournament_decision(&t).unwrap().unwrap();
+        model.save_tournament_decision(&t, &me, Decision { pick: Pick::to_opt(Pick::Left), ..decision.clone() }).unwrap();
+        assert_eq!(model.get_ratings(System::Elo).unwrap().len(), 2);
+
+        let undone = model.undo_last_decision(&t).unwrap().unwrap();
+        assert_eq!((undone.left_anime, undone.right_anime), (decision.left_anime, decision.right_anime));
+        assert!(model.get_ratings(System::Elo).unwrap().is_empty());
+        assert!(model.get_tournament_ballots(&t).unwrap().is_empty());
+    }
+
+    #[test]
+    fn an_undo_after_a_replayed_draw_keeps_the_rating_order() {
+        let model = mock_model();
+        let bracket = Format { mode: Mode::SingleElimination, ..Format::default() };
+        let first = new_tournament(&model, bracket);
+        let second = new_tournament(&model, bracket);
+        let me = model.find_member("me").unwrap();
+        let answer = |t: &Tournament, pick: Pick| {
+            let decision = model.next_tournament_decision(t).unwrap().unwrap();
+            model.save_tournament_decision(t, &me, Decision { pick: Pick::to_opt(pick), ..decision }).unwrap();
+        };
+
+        // the draw and its replay are both rated, for the one decision
+        answer(&first, Pick::Undecided);
+        answer(&first, Pick::Left);
+        answer(&second, Pick::Left);
+        answer(&first, Pick::Left);
+        answer(&first, Pick::Left);
+        assert_eq!(model.get_ratings(System::Elo).unwrap().len(), 10);
+
+        model.undo_last_decision(&first).unwrap();
+        let rated: Vec<i32> = model.get_ratings(System::Elo).unwrap().chunks(2)
+            .map(|pair| pair[0].tournament)
+            .collect();
+        assert_eq!(rated, vec![first.id, second.id, first.id]);
+    }
+
+    #[test]
+    fn an_anime_in_a_tournament_cant_be_removed() {
+        let model = mock_model();
+        let t = new_tournament(&model, Format::default());
+        let anime = model.get_tournament_anime(&t).unwrap()[0].clone();
+        assert!(model.remove_anime(anime.id).is_err());
+        assert!(model.get_anime_by_id(anime.id).is_ok());
+    }
+
+    #[test]
+    fn a_slot_in_a_tournament_cant_be_removed() {
+        let model = mock_model();
+        let t = new_tournament(&model, Format::default());
+        assert!(model.remove_slot(t.slot).is_err());
+        assert!(model.get_slot(t.slot).is_ok());
+    }
+
+    #[test]
+    fn a_failed_transaction_keeps_nothing() {
+        let model = mock_model();
+        let result: Result<()> = super::super::transaction(&model, || {
+            model.add_member("bo")?;
+            Err(TournamentError::Invalid("no".to_string()))
+        });
+        assert!(result.is_err());
+        assert!(model.find_member("bo").is_err());
+    }
+
+    #[test]
+    fn member_names_are_unique() {
+        let model = mock_model();
+        assert!(model.add_member("me").is_err());
+    }
+
+    #[test]
+    fn every_match_in_a_double_elimination_is_asked() {
+        let model = mock_model();
+        let t = new_tournament(&model, Format { mode: Mode::DoubleElimination, ..Format::default() });
+
+        // the lower id wins the first time two anime meet, and the higher one after
+        let asked = answer_all(&model, &t, |decision| {
+            let lower_left = decision.left_anime < decision.right_anime;
+            if lower_left == (decision.meeting == 0) { Pick::Left } else { Pick::Right }
+        });
+
+        // the winners' bracket side wins the final, so there's no bracket reset
+        assert_eq!(asked.len(), model.get_bracket_matches(&t).unwrap().len() - 1);
+        assert!(asked.iter().any(|decision| decision.meeting == 1));
+        assert_eq!(model.get_tournament(t.id).unwrap().status, Status::Finished);
+    }
+
+    #[test]
+    fn a_bracket_only_takes_the_seeds_it_has() {
+        let model = mock_model();
+        let slot = model.find_slot("first").unwrap();
+        let voters = model.get_members().unwrap();
+        let kaguya = model.get_anime().unwrap().into_iter().find(|a| a.name == "Kaguya S2").unwrap();
+
+        let format = Format { mode: Mode::SingleElimination, ..Format::default() };
+        assert!(model.add_tournament(&slot, None, format, &voters, &[kaguya.id]).is_err());
+        assert!(model.add_tournament(&slot, None, Format::default(), &voters, &[1]).is_err());
+        assert!(model.get_tournaments().unwrap().is_empty());
+    }
+}
@@ -1,382 +1,982 @@
-use diesel::prelude::*;
-use diesel::mysql::MysqlConnection;
-use diesel::sql_types::{BigInt};
-
+use chrono::{Local, NaiveDateTime};
 use dotenv::dotenv;
-use std::env;
+use rand::{thread_rng, Rng};
 use std::collections::HashMap;
-
-use rand::{thread_rng};
-use rand::seq::SliceRandom;
-
-// use crate::model::*;
+use std::env;
 
 pub mod schema;
 pub mod models;
+pub mod error;
+pub mod engine;
+pub mod ballots;
+pub mod results;
+pub mod rating;
+pub mod bradley_terry;
+pub mod database;
+pub mod memory;
 
 use models::*;
+pub use error::{TournamentError, Result};
+pub use database::Model;
+pub use memory::MemoryModel;
 
-fn establish_connection() -> MysqlConnection {
-    dotenv().ok();
+// how many letters there are in a room's join code
+pub const ROOM_CODE_LENGTH: usize = 6;
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    MysqlConnection::establish(&database_url).expect(&format!("Error connecting to {}", database_url))
-}
+/// Everything the tournament needs from storage.
+///
+/// Backends only provide the basic reads and writes; the tournament logic
+/// itself is shared in the provided methods, so every backend runs the
+/// tournament the same way.
+pub trait TournamentStore {
+    // Transactions, which can be nested. Use `transaction` rather than these,
+    // so every transaction begun is also ended.
+    fn begin_transaction(&self) -> Result<()>;
 
-pub struct Model {
-    pub connection: MysqlConnection
-}
+    fn commit_transaction(&self) -> Result<()>;
 
-#[derive(Debug,QueryableByName)]
-struct InsertId {
-    #[sql_type="BigInt"]
-    pub id: i64
-}
+    fn rollback_transaction(&self) -> Result<()>;
 
-impl Model {
-    pub fn connect() -> Model {
-        let connection = establish_connection();
+    // Anime
+    fn add_anime(&self, name: &str, episodes: Option<i32>) -> Result<Anime>;
 
-        Model {
-            connection: connection
-        }
-    }
+    fn get_anime(&self) -> Result<Vec<Anime>>;
+
+    fn get_anime_by_id(&self, anime_id: i32) -> Result<Anime>;
 
-    fn get_last_inserted_id(&self) -> i32 {
-        let result: Result<Vec<InsertId>,_> = diesel::sql_query("SELECT LAST_INSERT_ID() 'id';").load(&self.connection);
-        let insertid: i64 = match result {
-            Ok(results) => results.first().unwrap().id,
-            _ => 0
+    fn get_anime_for_slot(&self, slot: &Slot) -> Result<Vec<Anime>>;
+
+    fn update_anime(&self, anime: &Anime) -> Result<()>;
+
+    // only an anime that isn't in any tournament, so past results stay whole
+    fn remove_anime(&self, anime_id: i32) -> Result<()>;
+
+    // Slots
+    fn add_slot(&self, new_slot: &NewSlot) -> Result<Slot>;
+
+    // in showing order
+    fn get_slots(&self) -> Result<Vec<Slot>>;
+
+    fn get_slot(&self, slot_id: i32) -> Result<Slot>;
+
+    fn update_slot(&self, slot: &Slot) -> Result<()>;
+
+    // only a slot no tournament uses
+    fn remove_slot(&self, slot_id: i32) -> Result<()>;
+
+    fn get_anime_slots(&self, anime_id: i32) -> Result<Vec<Slot>>;
+
+    fn set_anime_slots(&self, anime_id: i32, slot_ids: &[i32]) -> Result<()>;
+
+    // find a slot by its name, or by its number in the showing order
+    fn find_slot(&self, name_or_position: &str) -> Result<Slot> {
+        let slots = self.get_slots()?;
+        let found = match name_or_position.parse::<i32>() {
+            Ok(position) => slots.into_iter().find(|slot| slot.position == position),
+            Err(_) => slots.into_iter().find(|slot| slot.name.eq_ignore_ascii_case(name_or_position))
         };
-        insertid as i32
+        found.ok_or_else(|| TournamentError::not_found_named("slot", name_or_position))
     }
 
-    pub fn add_anime(&self, name: &str, slot1: bool, slot2: bool, slot3: bool) -> Anime {
-        use schema::anime;
+    // Members
+    fn add_member(&self, name: &str) -> Result<Member>;
 
-        let new_anime = Anime {
-            id: 0,
-            name: name.to_string(),
-            episodes: None,
-            slot1, 
-            slot2,
-            slot3
-        };
+    fn get_members(&self) -> Result<Vec<Member>>;
 
-        diesel::insert_into(anime::table)
-            .values(&new_anime)
-            .execute(&self.connection)
-            .expect("Error saving new post");
+    fn get_member(&self, member_id: i32) -> Result<Member>;
 
-        new_anime
+    // find a member by name, or by id
+    fn find_member(&self, name_or_id: &str) -> Result<Member> {
+        let members = self.get_members()?;
+        let found = match name_or_id.parse::<i32>() {
+            Ok(id) => members.into_iter().find(|member| member.id == id),
+            Err(_) => members.into_iter().find(|member| member.name.eq_ignore_ascii_case(name_or_id))
+        };
+        found.ok_or_else(|| TournamentError::not_found_named("member", name_or_id))
     }
 
-    pub fn get_anime_by_id(&self, anime_id: i32) -> Option<Anime> {
-        use schema::anime::dsl::*;
+    // Tournament
+    fn create_tournament(&self, new_tournament: &NewTournament) -> Result<Tournament>;
+
+    fn update_tournament(&self, t: &Tournament) -> Result<()>;
+
+    fn get_tournaments(&self) -> Result<Vec<Tournament>>;
+
+    fn get_tournament(&self, tournament_id: i32) -> Result<Tournament>;
+
+    fn get_tournament_anime(&self, t: &Tournament) -> Result<Vec<Anime>>;
+
+    fn add_anime_to_tournament(&self, t: &Tournament, a: &Anime) -> Result<()>;
+
+    fn get_tournament_members(&self, t: &Tournament) -> Result<Vec<Member>>;
+
+    fn add_member_to_tournament(&self, t: &Tournament, m: &Member) -> Result<()>;
+
+    // in the order they were cast
+    fn get_tournament_ballots(&self, t: &Tournament) -> Result<Vec<Ballot>>;
+
+    fn insert_ballot(&self, ballot: &Ballot) -> Result<()>;
+
+    // every voter's ballot for a decision's pair, whichever way round it was
+    // asked, the same time they met
+    fn remove_ballots(&self, decision: &Decision) -> Result<()>;
+
+    // one voter's ballot for a pair, the way round it was asked
+    fn remove_ballot(&self, ballot: &Ballot) -> Result<()>;
+
+    // Ratings
+    fn add_rating(&self, new_rating: &NewRating) -> Result<()>;
+
+    // oldest first
+    fn get_ratings(&self, system: System) -> Result<Vec<Rating>>;
+
+    fn clear_ratings(&self) -> Result<()>;
+
+    // Brackets
+    fn add_bracket_match(&self, bracket_match: &BracketMatch) -> Result<()>;
+
+    // in the order they're played
+    fn get_bracket_matches(&self, t: &Tournament) -> Result<Vec<BracketMatch>>;
+
+    fn remove_bracket_matches(&self, t: &Tournament) -> Result<()>;
+
+    // Whole ballots
+    fn add_approval_ballot(&self, ballot: &ApprovalBallot) -> Result<()>;
+
+    // voter by voter
+    fn get_approval_ballots(&self, t: &Tournament) -> Result<Vec<ApprovalBallot>>;
+
+    fn add_ranked_ballot(&self, ballot: &RankedBallot) -> Result<()>;
+
+    // voter by voter, each best first
+    fn get_ranked_ballots(&self, t: &Tournament) -> Result<Vec<RankedBallot>>;
+
+    // Rooms
+    fn add_room(&self, room: &Room) -> Result<()>;
+
+    // its open matchup, and when that opened
+    fn update_room(&self, room: &Room) -> Result<()>;
 
-        anime.filter(id.eq(anime_id))
-            .first::<Anime>(&self.connection)
-            .optional().ok()?
+    fn get_room(&self, t: &Tournament) -> Result<Option<Room>>;
+
+    fn find_room(&self, code: &str) -> Result<Room>;
+
+    fn add_room_member(&self, room_member: &RoomMember) -> Result<()>;
+
+    fn get_room_members(&self, room: &Room) -> Result<Vec<Member>>;
+
+    fn add_room_ballot(&self, ballot: &RoomBallot) -> Result<()>;
+
+    // in the order they were cast
+    fn get_room_ballots(&self, room: &Room) -> Result<Vec<RoomBallot>>;
+
+    // A new tournament of the slot's anime. A bracket is seeded with `seeds`
+    // first, as with `seed_bracket`. Nothing is saved unless all of it can be.
+    fn add_tournament(&self, slot: &Slot, title: Option<&str>, format: Format, voters: &[Member], seeds: &[i32]) -> Result<Tournament> {
+        if format.mode == Mode::TopK && format.k.unwrap_or(0) < 1 {
+            return Err(TournamentError::Invalid("a top-k tournament needs k, how many anime to pick".to_string()));
+        }
+        if format.rounds.is_some() && (format.mode != Mode::Swiss || format.rounds.unwrap_or(0) < 1) {
+            return Err(TournamentError::Invalid("rounds is the number of rounds for a swiss tournament".to_string()));
+        }
+        if !seeds.is_empty() && format.mode != Mode::SingleElimination && format.mode != Mode::DoubleElimination {
+            return Err(TournamentError::Invalid("only a bracket tournament can be seeded".to_string()));
+        }
+        let anime = self.get_anime_for_slot(slot)?;
+        if let Some(seed) = seeds.iter().find(|seed| !anime.iter().any(|a| a.id == **seed)) {
+            return Err(TournamentError::Invalid(format!("anime #{} isn't in the {} slot", seed, slot.name)));
+        }
+
+        transaction(self, || {
+            let tournament = self.create_tournament(&NewTournament {
+                slot: slot.id,
+                title: title.map(|title| title.to_string()),
+                status: Status::Open,
+                created_at: Local::now().naive_local(),
+                mode: format.mode,
+                k: format.k,
+                pairing: format.pairing,
+                rounds: format.rounds,
+                seed: format.seed.unwrap_or_else(|| thread_rng().gen()),
+            })?;
+
+            // add the anime for the given slot
+            for a in &anime {
+                self.add_anime_to_tournament(&tournament, a)?;
+            }
+            for m in voters {
+                self.add_member_to_tournament(&tournament, m)?;
+            }
+            if tournament.is_bracket() {
+                self.seed_bracket(&tournament, seeds)?;
+            }
+            Ok(tournament)
+        })
     }
 
-    pub fn get_anime_for_slot(&self, slot: Slot) -> Vec<Anime> {
-        use schema::anime::dsl::*;
+    // Seed a bracket tournament's anime: the given anime first, in that order,
+    // then the rest by their Elo rating, and those without one by id. The bracket
+    // can only be seeded again until the first vote.
+    fn seed_bracket(&self, t: &Tournament, seeds: &[i32]) -> Result<()> {
+        if !t.is_bracket() {
+            return Err(TournamentError::Invalid(format!("tournament #{} isn't a bracket", t.id)));
+        }
+        if !self.get_tournament_ballots(t)?.is_empty() {
+            return Err(TournamentError::Invalid(format!("tournament #{} has already started", t.id)));
+        }
+        let anime = self.tournament_anime_ids(t)?;
+        if let Some(seed) = seeds.iter().find(|seed| !anime.contains(seed)) {
+            return Err(TournamentError::Invalid(format!("anime #{} is not in tournament #{}", seed, t.id)));
+        }
+
+        let rated: Vec<i32> = self.get_leaderboard(System::Elo)?.iter()
+            .map(|rating| rating.anime)
+            .filter(|a| anime.contains(a))
+            .collect();
+        let mut order: Vec<i32> = vec![];
+        for a in seeds.iter().chain(rated.iter()).chain(anime.iter()) {
+            if !order.contains(a) {
+                order.push(*a);
+            }
+        }
 
-        match slot {
-            Slot::First => anime.filter(slot1.eq(true))
-                .load::<Anime>(&self.connection)
-                .expect("Error loading anime"),
-            Slot::Second => anime.filter(slot2.eq(true))
-                .load::<Anime>(&self.connection)
-                .expect("Error loading anime"),
-            Slot::Third => anime.filter(slot3.eq(true))
-                .load::<Anime>(&self.connection)
-                .expect("Error loading anime"),
+        self.remove_bracket_matches(t)?;
+        for bracket_match in engine::bracket::build(t.id, &order, t.mode == Mode::DoubleElimination) {
+            self.add_bracket_match(&bracket_match)?;
         }
+        Ok(())
     }
-    
-    pub fn add_tournament(&self, slot: Slot) -> Tournament {
-        use schema::tournaments;
 
-        // create the tournament
-        let mut new_tournament = Tournament {
-            id: 0,
-        };
-        diesel::insert_into(tournaments::table)
-            .values(&new_tournament)
-            .execute(&self.connection)
-            .expect("Error saving new tournament");
+    fn tournament_voter_ids(&self, t: &Tournament) -> Result<Vec<i32>> {
+        Ok(self.get_tournament_members(t)?.iter().map(|m| m.id).collect())
+    }
 
-        new_tournament.id = self.get_last_inserted_id();
+    // the pairs every voter has answered, decided by majority, in the order
+    // they were first asked
+    fn get_tournament_decisions(&self, t: &Tournament) -> Result<Vec<Decision>> {
+        let ballots = self.get_tournament_ballots(t)?;
+        Ok(engine::votes::tally(&ballots, &self.tournament_voter_ids(t)?))
+    }
 
-        // add the anime for the given slot
-        let anime = self.get_anime_for_slot(slot);
-        for a in &anime {
-            self.add_anime_to_tournament(&new_tournament, a);
+    // in a fixed order, so the engine always replays the decisions the same way
+    fn tournament_anime_ids(&self, t: &Tournament) -> Result<Vec<i32>> {
+        let mut anime_ids: Vec<i32> = self.get_tournament_anime(t)?.iter().map(|anime| anime.id).collect();
+        anime_ids.sort_unstable();
+        Ok(anime_ids)
+    }
+
+    fn tournament_remaining_anime(&self, t: &Tournament) -> Result<Vec<i32>> {
+        let anime = self.tournament_anime_ids(t)?;
+        let decisions = self.get_tournament_decisions(t)?;
+        let matches = self.get_bracket_matches(t)?;
+        Ok(engine::remaining_anime(t, &anime, &decisions, &matches))
+    }
+
+    fn is_tournament_finished(&self, t: &Tournament) -> Result<bool> {
+        if t.mode.uses_ballots() {
+            return Ok(self.waiting_voters(t)?.is_empty());
         }
+        let anime = self.tournament_anime_ids(t)?;
+        let decisions = self.get_tournament_decisions(t)?;
+        let matches = self.get_bracket_matches(t)?;
+        // the ratings only change which pair comes next, not whether there is one
+        Ok(engine::next_pair(t, &anime, &decisions, &matches, &HashMap::new()).is_none())
+    }
 
-        new_tournament
+    // tournaments that were started but never finished, so can be resumed
+    fn get_unfinished_tournaments(&self) -> Result<Vec<Tournament>> {
+        let mut unfinished = self.get_tournaments()?;
+        unfinished.retain(|t| t.status == Status::Open);
+        Ok(unfinished)
     }
-    
-    // Tournament
-    pub fn is_tournament_finished(&self, t: &Tournament) -> bool {
-        let rem = self.tournament_remaining_anime(t);
-        // println!("[is finished] Remaining anime: {:?}", rem);
-        rem.len() <= 1
-    }
-
-    pub fn get_tournament_anime(&self, t: &Tournament) -> Vec<Anime> {
-        use schema::tournament_anime::dsl::*;
-        
-        let tuples = tournament_anime.filter(tournament.eq(t.id))
-            .load::<TournamentAnime>(&self.connection)
-            .expect("Error loading tournament anime");
-
-        let mut found: Vec<Anime> = Vec::with_capacity(tuples.len());
-        for tuple in &tuples {
-            let a = self.get_anime_by_id(tuple.anime);
-            match a {
-                Some(a) => found.push(a),
-                None => ()
-            }
+
+    fn abandon_tournament(&self, t: &Tournament) -> Result<Tournament> {
+        if t.status == Status::Finished {
+            return Err(TournamentError::AlreadyFinished(t.id));
         }
 
-        found
+        let mut abandoned = t.clone();
+        abandoned.status = Status::Abandoned;
+        self.update_tournament(&abandoned)?;
+        Ok(abandoned)
     }
 
-    pub fn add_anime_to_tournament(&self, t: &Tournament, a: &Anime) {
-        use schema::tournament_anime;
+    // an abandoned tournament can be picked up again where it was left
+    fn reopen_tournament(&self, t: &Tournament) -> Result<Tournament> {
+        if t.status == Status::Finished {
+            return Err(TournamentError::AlreadyFinished(t.id));
+        }
 
-        let new_tournament_anime = TournamentAnime {
-            tournament: t.id,
-            anime: a.id
-        };
-        diesel::insert_into(tournament_anime::table)
-            .values(&new_tournament_anime)
-            .execute(&self.connection)
-            .expect("Error adding anime to tournament");
+        let mut reopened = t.clone();
+        reopened.status = Status::Open;
+        self.update_tournament(&reopened)?;
+        Ok(reopened)
     }
 
-    pub fn get_tournament_decisions(&self, t: &Tournament) -> Vec<Decision> {
-        use schema::decisions::dsl::*;
+    // The anime's Elo ratings as they were when the tournament started, so the
+    // same answers always get the same questions.
+    fn starting_ratings(&self, t: &Tournament) -> Result<HashMap<i32, f64>> {
+        let mut ratings: HashMap<i32, f64> = HashMap::new();
+        for rating in self.get_ratings(System::Elo)? {
+            if rating.created_at <= t.created_at && rating.tournament != t.id {
+                ratings.insert(rating.anime, rating.rating);
+            }
+        }
+        Ok(ratings)
+    }
 
-        decisions.filter(tournament.eq(t.id))
-            .load::<Decision>(&self.connection)
-            .expect("Error loading decisions")
+    // The tournament's decisions in the order they were asked for, worked out
+    // again from its random seed and answers. Only the last answer counts for a
+    // pair that was asked again after a draw.
+    fn replay_tournament(&self, t: &Tournament) -> Result<Vec<Decision>> {
+        let anime = self.tournament_anime_ids(t)?;
+        let decisions = self.get_tournament_decisions(t)?;
+        let matches = self.get_bracket_matches(t)?;
+        let ratings = self.starting_ratings(t)?;
+
+        let mut asked: Vec<Decision> = vec![];
+        while let Some((left, right)) = engine::next_pair(t, &anime, &asked, &matches, &ratings) {
+            let meeting = engine::meeting(t, &asked, &matches, left, right).unwrap_or(0);
+            let decision = match engine::find_meeting(&decisions, meeting, left, right) {
+                Some(decision) => decision,
+                None => break
+            };
+            // waiting for a rematch
+            if engine::find_meeting(&asked, meeting, left, right).is_some() {
+                break;
+            }
+            asked.push(decision.clone());
+        }
+        Ok(asked)
     }
 
-    fn tournament_remaining_anime(&self, t: &Tournament) -> Vec<i32> {
-        // list of anime IDs
-        let tournament_anime = self.get_tournament_anime(t);
-        let decisions: Vec<Decision> = self.get_tournament_decisions(t);
-        let mut remaining: Vec<i32> = tournament_anime.iter().map(|anime| anime.id).collect();
+    // The next pair to ask about. A pair some voters have already answered comes
+    // first, so everyone answers the same pair before the tournament moves on.
+    // In a bracket it's the next match that's ready to play.
+    fn next_tournament_decision(&self, t: &Tournament) -> Result<Option<Decision>> {
+        let anime = self.tournament_anime_ids(t)?;
+        let ballots = self.get_tournament_ballots(t)?;
+        let voters = self.tournament_voter_ids(t)?;
+        let decisions = engine::votes::tally(&ballots, &voters);
+        let matches = self.get_bracket_matches(t)?;
+        let ratings = self.starting_ratings(t)?;
+
+        let pair = match engine::votes::open_pair(&ballots, &voters) {
+            Some(pair) => Some(pair),
+            None => engine::next_pair(t, &anime, &decisions, &matches, &ratings).map(|(left, right)| {
+                (left, right, engine::meeting(t, &decisions, &matches, left, right).unwrap_or(0))
+            })
+        };
 
-        // exclude anime based on picks
-        let mut remove: Vec<i32> = vec![];
-        for decision in &decisions {
-            match Pick::to_pick(decision.pick) {
-                Pick::Left => remove.push(decision.right_anime),
-                Pick::Right => remove.push(decision.left_anime),
-                Pick::Undecided => ()
+        Ok(pair.map(|(left, right, meeting)| {
+            // a rematch is asked the same way round as the draw it replaces
+            let (left, right) = match engine::find_meeting(&decisions, meeting, left, right) {
+                Some(drawn) => (drawn.left_anime, drawn.right_anime),
+                None => (left, right)
             };
+            Decision {
+                tournament: t.id,
+                left_anime: left,
+                right_anime: right,
+                pick: Pick::to_opt(Pick::Undecided),
+                meeting,
+            }
+        }))
+    }
+
+    // the tournament's voters who still have to answer the next pair
+    fn waiting_voters(&self, t: &Tournament) -> Result<Vec<Member>> {
+        let mut voters = self.get_tournament_members(t)?;
+        if t.mode.uses_ballots() {
+            let voted = self.ballot_voter_ids(t)?;
+            voters.retain(|voter| !voted.contains(&voter.id));
+            return Ok(voters);
+        }
+        let decision = match self.next_tournament_decision(t)? {
+            Some(decision) => decision,
+            None => return Ok(vec![])
+        };
+
+        // every ballot for a pair that drew will be replaced by the rematch
+        let decisions = self.get_tournament_decisions(t)?;
+        if engine::find_meeting(&decisions, decision.meeting, decision.left_anime, decision.right_anime).is_some() {
+            return Ok(voters);
         }
 
-        remaining.retain(|item| !remove.contains(item));
-        remaining
+        let ballots = self.get_tournament_ballots(t)?;
+        voters.retain(|voter| !ballots.iter().any(|ballot| ballot.voter == voter.id
+            && engine::votes::same_decision(ballot, &decision)));
+        Ok(voters)
     }
-    
-    // find the anime with the fewest picks
-    fn next_lowest_pick(&self, t: &Tournament, anime: Vec<i32>) -> Option<i32> {
-        let picks: Vec<i32> = self.get_tournament_decisions(t).iter().flat_map(|decision| {
-            match Pick::to_pick(decision.pick) {
-                Pick::Left => Some(decision.left_anime),
-                Pick::Right => Some(decision.right_anime),
-                Pick::Undecided => None
+
+    // One voter's answer. A ballot for a pair that drew before starts a rematch,
+    // and replaces everyone's ballots for it. In a bracket the pair has to be
+    // playing a match, and the ballot is for that meeting of theirs whatever
+    // `decision.meeting` says. The ballot, its ratings and the tournament's
+    // status are saved together or not at all.
+    fn save_tournament_decision(&self, t: &Tournament, voter: &Member, decision: Decision) -> Result<()> {
+        transaction(self, || {
+            if !self.tournament_voter_ids(t)?.contains(&voter.id) {
+                return Err(TournamentError::InvalidDecision(format!("{} isn't voting in tournament #{}", voter.name, t.id)));
             }
-        }).collect();
+            save_ballot(self, t, voter.id, &voter.name, &decision)
+        })
+    }
+
+    // the voters who have filled in their whole ballot
+    fn ballot_voter_ids(&self, t: &Tournament) -> Result<Vec<i32>> {
+        let mut voted: Vec<i32> = match t.mode {
+            Mode::Approval => self.get_approval_ballots(t)?.iter().map(|ballot| ballot.voter).collect(),
+            _ => self.get_ranked_ballots(t)?.iter().map(|ballot| ballot.voter).collect()
+        };
+        voted.dedup();
+        Ok(voted)
+    }
 
-        let mut num_decisions: HashMap<i32, i32> = HashMap::new();
-        for anime in &anime {
-            num_decisions.insert(*anime, 0);
+    // Check a voter can fill in a whole ballot of the kind the tournament's mode
+    // takes, returning the tournament's anime.
+    fn check_whole_ballot(&self, t: &Tournament, voter: &Member, modes: &[Mode]) -> Result<Vec<i32>> {
+        if t.status == Status::Finished {
+            return Err(TournamentError::AlreadyFinished(t.id));
+        }
+        if !modes.contains(&t.mode) {
+            return Err(TournamentError::InvalidDecision(format!("tournament #{} doesn't take that kind of ballot", t.id)));
+        }
+        if !self.tournament_voter_ids(t)?.contains(&voter.id) {
+            return Err(TournamentError::InvalidDecision(format!("{} isn't voting in tournament #{}", voter.name, t.id)));
         }
+        if self.ballot_voter_ids(t)?.contains(&voter.id) {
+            return Err(TournamentError::InvalidDecision(format!("{} has already voted in tournament #{}", voter.name, t.id)));
+        }
+        self.tournament_anime_ids(t)
+    }
 
-        for pick in picks {
-            let num = match num_decisions.get(&pick) {
-                Some(num) => num + 1,
-                None => 0
-            };
+    // One voter's approval ballot: every anime they'd watch. The tournament is
+    // finished once every voter has filled one in.
+    fn save_approval_ballot(&self, t: &Tournament, voter: &Member, approved: &[i32]) -> Result<()> {
+        let anime = self.check_whole_ballot(t, voter, &[Mode::Approval])?;
+        if let Some(anime_id) = approved.iter().find(|a| !anime.contains(a)) {
+            return Err(TournamentError::InvalidDecision(format!("anime #{} is not in tournament #{}", anime_id, t.id)));
+        }
 
-            if num > 0 {
-                num_decisions.insert(pick, num);
+        transaction(self, || {
+            for anime_id in &anime {
+                self.add_approval_ballot(&ApprovalBallot {
+                    tournament: t.id,
+                    voter: voter.id,
+                    anime: *anime_id,
+                    approved: approved.contains(anime_id),
+                })?;
             }
+            self.refresh_tournament_status(t)?;
+            Ok(())
+        })
+    }
+
+    // One voter's ranked ballot: every anime in the tournament, best first.
+    fn save_ranked_ballot(&self, t: &Tournament, voter: &Member, order: &[i32]) -> Result<()> {
+        let anime = self.check_whole_ballot(t, voter, &[Mode::Borda, Mode::InstantRunoff])?;
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        if sorted != anime {
+            return Err(TournamentError::InvalidDecision(format!("a ranked ballot must have each anime in tournament #{} once", t.id)));
         }
 
-        let lowest_pick_num: i32 = {
-            let mut lowest = 1000;
-            for pick_num in num_decisions.values() {
-                if *pick_num < lowest {
-                    lowest = *pick_num;   
-                }
+        transaction(self, || {
+            for (i, anime_id) in order.iter().enumerate() {
+                self.add_ranked_ballot(&RankedBallot {
+                    tournament: t.id,
+                    voter: voter.id,
+                    anime: *anime_id,
+                    position: i as i32 + 1,
+                })?;
             }
-            lowest
-        };
+            self.refresh_tournament_status(t)?;
+            Ok(())
+        })
+    }
 
-        let lowest_pick_anime: Vec<i32> = anime.iter().filter(|a| num_decisions[*a] == lowest_pick_num).map(|a| *a).collect();
-        
-        // None
-        let mut rng = thread_rng();
-        let pick = lowest_pick_anime.choose(&mut rng).map(|a| *a);
-        pick
+    // the whole ballots counted the way the tournament's mode counts them
+    fn get_tournament_tally(&self, t: &Tournament) -> Result<ballots::Tally> {
+        let anime = self.tournament_anime_ids(t)?;
+        match t.mode {
+            Mode::Approval => Ok(ballots::approval(&anime, &self.get_approval_ballots(t)?)),
+            Mode::Borda => Ok(ballots::borda(&anime, &self.get_ranked_ballots(t)?)),
+            Mode::InstantRunoff => Ok(ballots::instant_runoff(&anime, &self.get_ranked_ballots(t)?)),
+            _ => Err(TournamentError::Invalid(format!("tournament #{} has no whole ballots", t.id)))
+        }
     }
 
-    pub fn next_tournament_decision(&self, t: &Tournament) -> Option<Decision> {
-        let mut anime = self.tournament_remaining_anime(t);
-        if anime.len() < 2 {
-            return None
+    // Open a room for the tournament, for members to vote in from their phones.
+    // Each matchup is open to their votes for `seconds`. A tournament only has
+    // the one room, so opening it again gives the room it already has.
+    fn open_room(&self, t: &Tournament, seconds: i32) -> Result<Room> {
+        if let Some(room) = self.get_room(t)? {
+            return Ok(room);
+        }
+        if t.status == Status::Finished {
+            return Err(TournamentError::AlreadyFinished(t.id));
+        }
+        if t.mode.uses_ballots() {
+            return Err(TournamentError::Invalid(format!("tournament #{} takes whole ballots, not votes on pairs", t.id)));
+        }
+        if seconds < 1 {
+            return Err(TournamentError::Invalid("a matchup has to be open for at least a second".to_string()));
         }
 
-        let left = self.next_lowest_pick(t, anime.clone())?;
-        anime.retain(|a| *a != left);
-        let right = self.next_lowest_pick(t, anime)?;
+        // easy to read out and type on a phone, so no 0/O or 1/I
+        const LETTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+        let mut rng = thread_rng();
+        let code = loop {
+            let code: String = (0..ROOM_CODE_LENGTH).map(|_| LETTERS[rng.gen_range(0, LETTERS.len())] as char).collect();
+            match self.find_room(&code) {
+                Ok(_) => continue,
+                Err(TournamentError::NotFound { .. }) => break code,
+                Err(err) => return Err(err)
+            }
+        };
 
-        Some(Decision{
+        let room = Room {
             tournament: t.id,
-            left_anime: left,
-            right_anime: right,
-            pick: Pick::to_opt(Pick::Undecided)
-        })
+            code,
+            seconds,
+            matchup: 1,
+            opened_at: Local::now().naive_local(),
+        };
+        self.add_room(&room)?;
+        Ok(room)
     }
-    
-    pub fn save_tournament_decision(&self, t: &Tournament, new_decision: Decision) {
-        use schema::decisions;
 
-        // let new_decision = Decision {
-        //     tournament: t.id,
-        //     anime: a.id
-        // };
-        diesel::insert_into(decisions::table)
-            .values(&new_decision)
-            .execute(&self.connection)
-            .expect("Error adding decision to tournament");
+    fn join_room(&self, room: &Room, member: &Member) -> Result<()> {
+        if self.get_room_members(room)?.iter().any(|m| m.id == member.id) {
+            return Ok(());
+        }
+        self.add_room_member(&RoomMember { tournament: room.tournament, member: member.id })
     }
 
-    pub fn get_tournament_winner(&self, t: &Tournament) -> Option<i32> {
-        let anime: Vec<i32> = self.tournament_remaining_anime(t);
-        if anime.len() > 1 {
-            return None;
+    // One member's vote on the room's open matchup, which has to be the pair
+    // they were shown: a matchup that has closed takes no more votes.
+    fn cast_room_ballot(&self, room: &Room, voter: &Member, left_anime: i32, right_anime: i32, pick: Pick) -> Result<()> {
+        let t = self.get_tournament(room.tournament)?;
+        let room = self.refresh_room(room)?;
+        let decision = match self.next_tournament_decision(&t)? {
+            Some(decision) => decision,
+            None if self.is_tournament_finished(&t)? => return Err(TournamentError::AlreadyFinished(t.id)),
+            None => return Err(TournamentError::InvalidDecision(format!("room {} has nothing to vote on", room.code)))
+        };
+
+        if (decision.left_anime, decision.right_anime) != (left_anime, right_anime) {
+            return Err(TournamentError::InvalidDecision(format!("voting on anime #{} and #{} has closed", left_anime, right_anime)));
+        }
+        if !self.get_room_members(&room)?.iter().any(|m| m.id == voter.id) {
+            return Err(TournamentError::InvalidDecision(format!("{} isn't in room {}", voter.name, room.code)));
+        }
+        if self.get_room_ballots(&room)?.iter().any(|ballot| ballot.matchup == room.matchup && ballot.voter == voter.id) {
+            return Err(TournamentError::InvalidDecision(format!("{} has already voted on anime #{} and #{}", voter.name, left_anime, right_anime)));
         }
 
-        anime.first().map(|a| *a)
+        self.add_room_ballot(&RoomBallot {
+            tournament: t.id,
+            matchup: room.matchup,
+            voter: voter.id,
+            left_anime,
+            right_anime,
+            pick: Pick::to_opt(pick),
+            cast_at: Local::now().naive_local(),
+        })?;
+
+        // that may have been the last vote
+        self.refresh_room(&room)?;
+        Ok(())
     }
-}
 
+    // Close the room's matchup once its time is up or everyone in the room has
+    // voted: the majority, or a draw on a tie, is saved as the room's one ballot
+    // for the pair, which stands for all of the tournament's voters, and the
+    // next matchup opens. The members' own votes stay with the room. A matchup
+    // nobody voted on is open for another go. Returns the room as it is now.
+    fn refresh_room(&self, room: &Room) -> Result<Room> {
+        let t = self.get_tournament(room.tournament)?;
+        let decision = match self.next_tournament_decision(&t)? {
+            Some(decision) => decision,
+            None => return Ok(room.clone())
+        };
+        let ballots: Vec<Ballot> = self.get_room_ballots(room)?.iter()
+            .filter(|ballot| ballot.matchup == room.matchup)
+            .map(RoomBallot::ballot)
+            .collect();
+        let voted: Vec<i32> = ballots.iter().map(|ballot| ballot.voter).collect();
+        let now = Local::now().naive_local();
+
+        let mut next = room.clone();
+        next.opened_at = now;
+        // the pair was decided some other way, such as from the command line
+        if ballots.iter().any(|ballot| !engine::votes::same_pair(ballot, decision.left_anime, decision.right_anime)) {
+            next.matchup += 1;
+            self.update_room(&next)?;
+            return Ok(next);
+        }
 
+        let members = self.get_room_members(room)?;
+        let everyone_voted = !members.is_empty() && members.iter().all(|m| voted.contains(&m.id));
+        if !everyone_voted && now < room.closes_at() {
+            return Ok(room.clone());
+        }
 
+        if let Some(majority) = engine::votes::tally(&ballots, &voted).into_iter().next() {
+            next.matchup += 1;
+            let name = format!("room {}", room.code);
+            transaction(self, || {
+                save_ballot(self, &t, ROOM_VOTER, &name, &Decision { pick: majority.pick, ..decision.clone() })?;
+                self.update_room(&next)
+            })?;
+            return Ok(next);
+        }
+        self.update_room(&next)?;
+        Ok(next)
+    }
 
+    // Take back the last ballot cast in the tournament, so that pair is asked
+    // again. Returns the ballot that was taken back, if there was one.
+    //
+    // A rematch after a draw deletes the ballots for the draw, so taking back
+    // the rematch's first ballot leaves the pair open rather than drawn again:
+    // the drawn ballots can't be brought back.
+    fn undo_last_decision(&self, t: &Tournament) -> Result<Option<Ballot>> {
+        let last = match self.get_tournament_ballots(t)?.pop() {
+            Some(ballot) => ballot,
+            None => return Ok(None)
+        };
 
-/*
-impl Tournament {
+        transaction(self, || {
+            self.remove_ballot(&last)?;
+            self.rebuild_ratings()?;
+            self.refresh_tournament_status(t)?;
+            Ok(())
+        })?;
+        Ok(Some(last))
+    }
 
+    // Change one voter's answer for a pair, even once the tournament is
+    // finished, the last time they met. Whatever comes next is worked out again
+    // from the new answer.
+    fn amend_decision(&self, t: &Tournament, voter: &Member, left_anime: i32, right_anime: i32, pick: Pick) -> Result<()> {
+        let ballots = self.get_tournament_ballots(t)?;
+        let ballot = ballots.iter().rev()
+            .find(|ballot| ballot.voter == voter.id && engine::votes::same_pair(ballot, left_anime, right_anime))
+            .ok_or_else(|| TournamentError::InvalidDecision(format!("{} hasn't voted on anime #{} and #{}", voter.name, left_anime, right_anime)))?;
+
+        transaction(self, || {
+            // replaced rather than updated, as the pair may be the other way round now
+            self.remove_ballot(ballot)?;
+            self.insert_ballot(&Ballot {
+                tournament: t.id,
+                left_anime,
+                right_anime,
+                pick: Pick::to_opt(pick),
+                voter: voter.id,
+                number: ballot.number,
+                meeting: ballot.meeting,
+            })?;
+            self.rebuild_ratings()?;
+            self.refresh_tournament_status(t)?;
+            Ok(())
+        })
+    }
 
-    
-    pub fn remaining_anime(&self) -> Vec<i32> {
-        // list of anime IDs
-        let mut remaining: Vec<i32> = self.items.iter().map(|anime| anime.id).collect();
+    // finished or open again, to match the decisions after one was changed
+    fn refresh_tournament_status(&self, t: &Tournament) -> Result<Tournament> {
+        let mut refreshed = self.get_tournament(t.id)?;
+        let finished = self.is_tournament_finished(&refreshed)?;
+        if finished && refreshed.status != Status::Finished {
+            refreshed.status = Status::Finished;
+            refreshed.finished_at = Some(Local::now().naive_local());
+        } else if !finished && refreshed.status == Status::Finished {
+            refreshed.status = Status::Open;
+            refreshed.finished_at = None;
+        } else {
+            return Ok(refreshed);
+        }
+        self.update_tournament(&refreshed)?;
+        Ok(refreshed)
+    }
 
-        // exclude anime based on picks
-        let mut remove: Vec<i32> = vec![];
-        for decision in &self.decisions {
-            match decision.pick {
-                Pick::Left => remove.push(decision.right),
-                Pick::Right => remove.push(decision.left),
-                Pick::Undecided => ()
-            };
+    fn get_tournament_winner(&self, t: &Tournament) -> Result<Option<i32>> {
+        if t.mode.uses_ballots() {
+            if !self.is_tournament_finished(t)? {
+                return Ok(None);
+            }
+            return Ok(self.get_tournament_tally(t)?.winner);
         }
+        let anime = self.tournament_anime_ids(t)?;
+        let decisions = self.get_tournament_decisions(t)?;
+        let matches = self.get_bracket_matches(t)?;
+        Ok(engine::winner(t, &anime, &decisions, &matches))
+    }
+
+    // Best first. Until a ranking tournament is finished this only has the
+    // anime that have been placed so far.
+    fn get_tournament_ranking(&self, t: &Tournament) -> Result<Vec<Anime>> {
+        let anime = self.get_tournament_anime(t)?;
+        let anime_ids = self.tournament_anime_ids(t)?;
+        let ranked = if t.mode.uses_ballots() {
+            self.get_tournament_tally(t)?.standings.iter().map(|(anime_id, _)| *anime_id).collect()
+        } else {
+            let decisions = self.get_tournament_decisions(t)?;
+            let matches = self.get_bracket_matches(t)?;
+            engine::ranking(t, &anime_ids, &decisions, &matches)
+        };
+
+        Ok(ranked.iter()
+            .filter_map(|anime_id| anime.iter().find(|a| a.id == *anime_id).cloned())
+            .collect())
+    }
 
-        // println!(" * Remaining anime: {:?} - {:?}", remaining, remove);
-        remaining.retain(|item| !remove.contains(item));
-        remaining
+    // a Swiss or round robin tournament's standings, best first
+    fn get_tournament_standings(&self, t: &Tournament) -> Result<Vec<engine::Standing>> {
+        let anime = self.tournament_anime_ids(t)?;
+        let decisions = self.get_tournament_decisions(t)?;
+        engine::standings(t, &anime, &decisions)
+            .ok_or_else(|| TournamentError::Invalid(format!("tournament #{} has no standings", t.id)))
     }
 
-    pub fn is_finished(&self) -> bool {
-        let rem = self.remaining_anime();
-        // println!("[is finished] Remaining anime: {:?}", rem);
-        rem.len() <= 1
+    // how each anime did against each other one
+    fn get_head_to_head(&self, t: &Tournament) -> Result<engine::round_robin::HeadToHead> {
+        let anime = self.tournament_anime_ids(t)?;
+        let decisions = self.get_tournament_decisions(t)?;
+        Ok(engine::round_robin::HeadToHead::new(&anime, &decisions))
     }
 
-    // find the anime with the fewest picks
-    fn next_lowest_pick(&self, anime: Vec<i32>) -> Option<i32> {
-        let picks: Vec<i32> = self.decisions.iter().flat_map(|decision| {
-            match decision.pick {
-                Pick::Left => Some(decision.left),
-                Pick::Right => Some(decision.right),
-                Pick::Undecided => None
+    fn rate_decision(&self, decision: &Decision, created_at: NaiveDateTime) -> Result<()> {
+        let left_score = match Pick::to_pick(decision.pick) {
+            Pick::Left => 1.0,
+            Pick::Right => 0.0,
+            Pick::Undecided => 0.5
+        };
+        let current = |ratings: &[Rating], anime_id: i32, system: System| ratings.iter()
+            .rev()
+            .find(|rating| rating.anime == anime_id)
+            .map(rating::Strength::of)
+            .unwrap_or_else(|| rating::initial(system));
+
+        for system in &[System::Elo, System::Glicko2] {
+            let ratings = self.get_ratings(*system)?;
+            let left = current(&ratings, decision.left_anime, *system);
+            let right = current(&ratings, decision.right_anime, *system);
+
+            let updates = [
+                (decision.left_anime, rating::update(*system, left, right, left_score)),
+                (decision.right_anime, rating::update(*system, right, left, 1.0 - left_score)),
+            ];
+            for (anime_id, strength) in &updates {
+                self.add_rating(&NewRating {
+                    anime: *anime_id,
+                    system: *system,
+                    rating: strength.rating,
+                    deviation: strength.deviation,
+                    volatility: strength.volatility,
+                    tournament: decision.tournament,
+                    created_at,
+                    meeting: decision.meeting,
+                })?;
             }
-        }).collect();
+        }
+        Ok(())
+    }
 
-        let mut num_decisions: HashMap<i32, i32> = HashMap::new();
-        for anime in &anime {
-            num_decisions.insert(*anime, 0);
+    // every anime's current rating, best first
+    fn get_leaderboard(&self, system: System) -> Result<Vec<Rating>> {
+        let mut latest: HashMap<i32, Rating> = HashMap::new();
+        for rating in self.get_ratings(system)? {
+            latest.insert(rating.anime, rating);
         }
 
-        for pick in picks {
-            let num = match num_decisions.get(&pick) {
-                Some(num) => num + 1,
-                None => 0
-            };
+        let mut leaderboard: Vec<Rating> = latest.into_values().collect();
+        leaderboard.sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(leaderboard)
+    }
 
-            if num > 0 {
-                num_decisions.insert(pick, num);
+    // oldest first
+    fn get_rating_history(&self, anime_id: i32, system: System) -> Result<Vec<Rating>> {
+        let mut history = self.get_ratings(system)?;
+        history.retain(|rating| rating.anime == anime_id);
+        Ok(history)
+    }
+
+    // Rate every decision again from the start, such as after one was changed,
+    // so the change carries through to every rating after it. The decisions are
+    // rated in the order they were last rated in, each at the time it last was,
+    // as the starting ratings of later tournaments depend on that. Those that
+    // haven't been rated before come last, tournament by tournament.
+    fn rebuild_ratings(&self) -> Result<()> {
+        // two Elo ratings each time a decision was rated, one for each anime; a
+        // draw played again is rated again, so the later ratings count
+        let mut rated_at: HashMap<(i32, i32, i32, i32), (usize, NaiveDateTime)> = HashMap::new();
+        for (i, pair) in self.get_ratings(System::Elo)?.chunks(2).enumerate() {
+            if let [left, right] = pair {
+                rated_at.insert(rating_key(left.tournament, left.anime, right.anime, left.meeting), (i, left.created_at));
             }
         }
 
-        let lowest_pick_num: i32 = {
-            let mut lowest = 1000;
-            for pick_num in num_decisions.values() {
-                if *pick_num < lowest {
-                    lowest = *pick_num;   
+        let mut rated = vec![];
+        let mut unrated = vec![];
+        for t in &self.get_tournaments()? {
+            for decision in self.get_tournament_decisions(t)? {
+                let key = rating_key(decision.tournament, decision.left_anime, decision.right_anime, decision.meeting);
+                match rated_at.get(&key) {
+                    Some((i, created_at)) => rated.push((*i, *created_at, decision)),
+                    None => unrated.push(decision)
                 }
             }
-            lowest
-        };
+        }
+        rated.sort_by_key(|(i, _, _)| *i);
 
-        let lowest_pick_anime: Vec<i32> = anime.iter().filter(|a| num_decisions[*a] == lowest_pick_num).map(|a| *a).collect();
-        
-        // None
-        let mut rng = thread_rng();
-        let pick = lowest_pick_anime.choose(&mut rng).map(|a| *a);
-        pick
+        transaction(self, || {
+            self.clear_ratings()?;
+            for (_, created_at, decision) in &rated {
+                self.rate_decision(decision, *created_at)?;
+            }
+            let now = Local::now().naive_local();
+            for decision in &unrated {
+                self.rate_decision(decision, now)?;
+            }
+            Ok(())
+        })
     }
 
-    pub fn next_decision(&self) -> Option<Decision> {
-        let mut anime = self.remaining_anime();
-        if anime.len() < 2 {
-            return None
+    // a Bradley-Terry fit over every ballot in the given tournaments
+    fn fit_bradley_terry(&self, tournaments: &[Tournament]) -> Result<bradley_terry::Fit> {
+        let mut anime: Vec<i32> = vec![];
+        let mut ballots: Vec<Ballot> = vec![];
+        for t in tournaments {
+            anime.extend(self.tournament_anime_ids(t)?);
+            ballots.extend(self.get_tournament_ballots(t)?);
         }
+        anime.sort_unstable();
+        anime.dedup();
 
-        let left = self.next_lowest_pick(anime.clone())?;
-        anime.retain(|a| *a != left);
-        let right = self.next_lowest_pick(anime)?;
+        Ok(bradley_terry::fit(&anime, &ballots))
+    }
 
-        Some(Decision{
-            left: left,
-            right: right,
-            pick: Pick::Undecided
-        })
+    // every ballot in the tournament, counted pair by pair
+    fn get_tournament_matrix(&self, t: &Tournament) -> Result<results::Matrix> {
+        let anime = self.tournament_anime_ids(t)?;
+        let ballots = self.get_tournament_ballots(t)?;
+        Ok(results::Matrix::new(&anime, &ballots))
     }
 
-    pub fn add_decision(&mut self, decision: Decision) {
-        self.decisions.push(decision);
+    // the best k anime in order, or the whole ranking if the tournament has no k
+    fn get_tournament_top_k(&self, t: &Tournament) -> Result<Vec<Anime>> {
+        let mut ranking = self.get_tournament_ranking(t)?;
+        if let Some(k) = t.k {
+            ranking.truncate(k.max(0) as usize);
+        }
+        Ok(ranking)
     }
+}
 
-    pub fn get_winner(&self) -> Option<i32> {
-        let anime: Vec<i32> = self.remaining_anime();
-        if anime.len() > 1 {
-            return None;
+// the decision a rating is from, whichever way round the pair was asked
+fn rating_key(tournament: i32, a: i32, b: i32, meeting: i32) -> (i32, i32, i32, i32) {
+    (tournament, a.min(b), a.max(b), meeting)
+}
+
+// Run `work` as one transaction, handing back what it returns: if it fails,
+// none of its writes are kept
+pub fn transaction<S: TournamentStore + ?Sized, T>(store: &S, work: impl FnOnce() -> Result<T>) -> Result<T> {
+    store.begin_transaction()?;
+    match work() {
+        Ok(value) => {
+            store.commit_transaction()?;
+            Ok(value)
+        }
+        Err(err) => {
+            store.rollback_transaction()?;
+            Err(err)
         }
+    }
+}
+
+// Save `voter`'s ballot for the pair, checking it's one the tournament can take
+// now, as for `save_tournament_decision`. The caller runs it in a transaction.
+fn save_ballot<S: TournamentStore + ?Sized>(store: &S, t: &Tournament, voter: i32, voter_name: &str, decision: &Decision) -> Result<()> {
+    if t.status == Status::Finished || store.is_tournament_finished(t)? {
+        return Err(TournamentError::AlreadyFinished(t.id));
+    }
+    let remaining = store.tournament_remaining_anime(t)?;
 
-        anime.first().map(|a| *a)
+    if decision.tournament != t.id {
+        return Err(TournamentError::InvalidDecision(format!("decision is for tournament #{}, not #{}", decision.tournament, t.id)));
+    }
+    if decision.left_anime == decision.right_anime {
+        return Err(TournamentError::InvalidDecision(format!("anime #{} can't be compared with itself", decision.left_anime)));
+    }
+    for anime_id in &[decision.left_anime, decision.right_anime] {
+        if !remaining.contains(anime_id) {
+            return Err(TournamentError::InvalidDecision(format!("anime #{} is not still in tournament #{}", anime_id, t.id)));
+        }
+    }
+    let decisions = store.get_tournament_decisions(t)?;
+    let (left, right) = (decision.left_anime, decision.right_anime);
+    let meeting = engine::meeting(t, &decisions, &store.get_bracket_matches(t)?, left, right)
+        .ok_or_else(|| TournamentError::InvalidDecision(format!("anime #{} and #{} aren't playing a match", left, right)))?;
+    let decision = Decision { meeting, ..decision.clone() };
+    if engine::beats_at(&decisions, meeting, left, right).is_some() {
+        return Err(TournamentError::InvalidDecision(format!("anime #{} and #{} have already been decided", left, right)));
     }
+
+    if engine::find_meeting(&decisions, meeting, left, right).is_some() {
+        store.remove_ballots(&decision)?;
+    } else {
+        let ballots = store.get_tournament_ballots(t)?;
+        let voted = ballots.iter().any(|ballot| ballot.voter == voter
+            && engine::votes::same_decision(ballot, &decision));
+        if voted {
+            return Err(TournamentError::InvalidDecision(format!("{} has already voted on anime #{} and #{}", voter_name, decision.left_anime, decision.right_anime)));
+        }
+    }
+
+    let number = store.get_tournament_ballots(t)?.iter().map(|ballot| ballot.number).max().unwrap_or(0) + 1;
+    store.insert_ballot(&Ballot {
+        tournament: decision.tournament,
+        left_anime: decision.left_anime,
+        right_anime: decision.right_anime,
+        pick: decision.pick,
+        voter,
+        number,
+        meeting,
+    })?;
+
+    // that was the last ballot for the pair
+    let decisions = store.get_tournament_decisions(t)?;
+    if let Some(decided) = engine::find_meeting(&decisions, meeting, left, right) {
+        store.rate_decision(decided, Local::now().naive_local())?;
+    }
+
+    // that was the last decision
+    if store.is_tournament_finished(t)? {
+        let mut finished = t.clone();
+        finished.status = Status::Finished;
+        finished.finished_at = Some(Local::now().naive_local());
+        store.update_tournament(&finished)?;
+    }
+    Ok(())
 }
 
-*/
+// the `DATABASE_URL` that asks for the in-memory store
+pub const MEMORY_URL: &str = "memory:";
+
+/// Opens the database named by `DATABASE_URL`. `memory:` opens an in-memory
+/// store with the mock anime instead, which loses everything on exit.
+pub fn open_store() -> Result<Box<dyn TournamentStore + Send>> {
+    dotenv().ok();
+
+    let database_url = env::var("DATABASE_URL")
+        .map_err(|_| TournamentError::Connection(format!("DATABASE_URL isn't set: set it to a database, or to {} to try things out in memory", MEMORY_URL)))?;
+    if database_url == MEMORY_URL {
+        eprintln!("Warning: using an in-memory store, so nothing will be saved");
+        let model = MemoryModel::new();
+        model.add_mock_anime()?;
+        return Ok(Box::new(model));
+    }
+    Ok(Box::new(Model::connect(&database_url)?))
+}
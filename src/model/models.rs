@@ -1,16 +1,84 @@
-use crate::schema::*;
+use std::io::Write;
+use std::str::FromStr;
 
-#[derive(Debug)]
-pub enum Slot {
-    First,
-    Second,
-    Third
+use chrono::NaiveDateTime;
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::schema::*;
+
+// Write an enum by name in JSON, using its `name` and `FromStr`
+macro_rules! json_name {
+    ($type:ty) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.name())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                name.parse().map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+// Store an enum by name in a varchar column, using its `name` and `FromStr`
+macro_rules! text_column {
+    ($type:ty) => {
+        json_name!($type);
+
+        impl<DB: Backend> ToSql<Text, DB> for $type where str: ToSql<Text, DB> {
+            fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+                self.name().to_sql(out)
+            }
+        }
+
+        impl<DB: Backend> FromSql<Text, DB> for $type where String: FromSql<Text, DB> {
+            fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+                let name = String::from_sql(bytes)?;
+                Ok(name.parse()?)
+            }
+        }
+    };
 }
 
-#[derive(Debug)]
+// a showing slot in a meeting, such as "first" or "late"
+#[derive(Queryable,Debug,Clone,PartialEq)]
+pub struct Slot {
+    pub id: i32,
+    pub name: String,
+    pub position: i32,
+    pub duration: Option<i32>,
+    pub max_episodes: Option<i32>,
+}
+
+#[derive(Insertable,Debug)]
+#[table_name="slots"]
+pub struct NewSlot {
+    pub name: String,
+    pub position: i32,
+    pub duration: Option<i32>,
+    pub max_episodes: Option<i32>,
+}
+
+#[derive(Queryable,Insertable,Debug,Clone)]
+#[table_name="anime_slots"]
+pub struct AnimeSlot {
+    pub anime: i32,
+    pub slot: i32
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
 pub enum Pick {
     Left,
     Right,
+    // couldn't choose between them, which counts as a draw
     Undecided
 }
 
@@ -30,43 +98,536 @@ impl Pick {
             Pick::Undecided => None
         }
     }
-    
+
     pub fn name(&self) -> &str {
         match self {
             Pick::Left => "left",
             Pick::Right => "right",
-            Pick::Undecided => "undecided"
+            Pick::Undecided => "draw"
         }
     }
 }
 
-#[derive(Queryable, Insertable,Debug)]
+impl FromStr for Pick {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Pick, String> {
+        match s {
+            "left" => Ok(Pick::Left),
+            "right" => Ok(Pick::Right),
+            "draw" => Ok(Pick::Undecided),
+            _ => Err(format!("Unknown pick: {}", s))
+        }
+    }
+}
+
+json_name!(Pick);
+
+#[derive(Queryable,Insertable,Debug,Clone,Serialize)]
 #[table_name="anime"]
 pub struct Anime {
     pub id: i32,
     pub name: String,
     pub episodes: Option<i32>,
-    pub slot1: bool,
-    pub slot2: bool,
-    pub slot3: bool,
 }
 
-#[derive(Queryable, Insertable,Debug)]
+#[derive(Debug,Clone,Copy,PartialEq,AsExpression,FromSqlRow)]
+#[sql_type="Text"]
+pub enum Status {
+    Open,
+    Finished,
+    Abandoned
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Open => "open",
+            Status::Finished => "finished",
+            Status::Abandoned => "abandoned",
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Status, String> {
+        match s {
+            "open" => Ok(Status::Open),
+            "finished" => Ok(Status::Finished),
+            "abandoned" => Ok(Status::Abandoned),
+            _ => Err(format!("Unknown tournament status: {}", s))
+        }
+    }
+}
+
+text_column!(Status);
+
+#[derive(Debug,Clone,Copy,PartialEq,AsExpression,FromSqlRow)]
+#[sql_type="Text"]
+pub enum Mode {
+    Elimination,
+    Ranking,
+    TopK,
+    SingleElimination,
+    DoubleElimination,
+    Swiss,
+    RoundRobin,
+    Approval,
+    Borda,
+    InstantRunoff
+}
+
+impl Mode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Elimination => "elimination",
+            Mode::Ranking => "ranking",
+            Mode::TopK => "top-k",
+            Mode::SingleElimination => "single-elimination",
+            Mode::DoubleElimination => "double-elimination",
+            Mode::Swiss => "swiss",
+            Mode::RoundRobin => "round-robin",
+            Mode::Approval => "approval",
+            Mode::Borda => "borda",
+            Mode::InstantRunoff => "instant-runoff",
+        }
+    }
+
+    // whether anime go out as the tournament goes on, rather than all being
+    // ranked at the end
+    pub fn knocks_out(&self) -> bool {
+        !matches!(self, Mode::Ranking | Mode::Swiss | Mode::RoundRobin) && !self.uses_ballots()
+    }
+
+    // whether each voter fills in one whole ballot rather than picking pairs
+    pub fn uses_ballots(&self) -> bool {
+        matches!(self, Mode::Approval | Mode::Borda | Mode::InstantRunoff)
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mode, String> {
+        match s {
+            "elimination" => Ok(Mode::Elimination),
+            "ranking" => Ok(Mode::Ranking),
+            "top-k" => Ok(Mode::TopK),
+            "single-elimination" => Ok(Mode::SingleElimination),
+            "double-elimination" => Ok(Mode::DoubleElimination),
+            "swiss" => Ok(Mode::Swiss),
+            "round-robin" => Ok(Mode::RoundRobin),
+            "approval" => Ok(Mode::Approval),
+            "borda" => Ok(Mode::Borda),
+            "instant-runoff" => Ok(Mode::InstantRunoff),
+            _ => Err(format!("Unknown tournament mode: {}", s))
+        }
+    }
+}
+
+text_column!(Mode);
+
+// how an elimination tournament picks its next pair
+#[derive(Debug,Clone,Copy,PartialEq,AsExpression,FromSqlRow)]
+#[sql_type="Text"]
+pub enum Pairing {
+    LowestPick,
+    Bracket,
+    Swiss,
+    InformationGain
+}
+
+impl Pairing {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pairing::LowestPick => "lowest-pick",
+            Pairing::Bracket => "bracket",
+            Pairing::Swiss => "swiss",
+            Pairing::InformationGain => "info-gain",
+        }
+    }
+}
+
+impl FromStr for Pairing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Pairing, String> {
+        match s {
+            "lowest-pick" => Ok(Pairing::LowestPick),
+            "bracket" => Ok(Pairing::Bracket),
+            "swiss" => Ok(Pairing::Swiss),
+            "info-gain" => Ok(Pairing::InformationGain),
+            _ => Err(format!("Unknown pairing: {}", s))
+        }
+    }
+}
+
+text_column!(Pairing);
+
+// the part of a double elimination bracket a match is in
+#[derive(Debug,Clone,Copy,PartialEq,AsExpression,FromSqlRow)]
+#[sql_type="Text"]
+pub enum Bracket {
+    Winners,
+    Losers,
+    Final
+}
+
+impl Bracket {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bracket::Winners => "winners",
+            Bracket::Losers => "losers",
+            Bracket::Final => "final",
+        }
+    }
+}
+
+impl FromStr for Bracket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Bracket, String> {
+        match s {
+            "winners" => Ok(Bracket::Winners),
+            "losers" => Ok(Bracket::Losers),
+            "final" => Ok(Bracket::Final),
+            _ => Err(format!("Unknown bracket: {}", s))
+        }
+    }
+}
+
+text_column!(Bracket);
+
+#[derive(Queryable,Debug,Clone,Serialize)]
 pub struct Tournament {
     pub id: i32,
+    pub slot: i32,
+    pub title: Option<String>,
+    pub status: Status,
+    pub created_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub mode: Mode,
+    pub k: Option<i32>,
+    pub pairing: Pairing,
+    pub rounds: Option<i32>,
+    // where the tournament's random choices come from
+    pub seed: i64,
 }
 
-#[derive(Queryable,Insertable,Debug)]
+impl Tournament {
+    pub fn name(&self) -> String {
+        match &self.title {
+            Some(title) => format!("#{} {}", self.id, title),
+            None => format!("#{}", self.id)
+        }
+    }
+
+    pub fn mode_name(&self) -> String {
+        match (self.mode, self.k) {
+            (Mode::TopK, Some(k)) => format!("top {}", k),
+            (Mode::Swiss, _) => match self.rounds {
+                Some(rounds) => format!("swiss, {} rounds", rounds),
+                None => String::from("swiss")
+            },
+            (mode, _) => mode.name().to_string()
+        }
+    }
+
+    // whether the anime were seeded into bracket matches
+    pub fn is_bracket(&self) -> bool {
+        self.mode == Mode::SingleElimination || self.mode == Mode::DoubleElimination
+    }
+}
+
+// how a new tournament is run
+#[derive(Debug,Clone,Copy)]
+pub struct Format {
+    pub mode: Mode,
+    // how many anime a top-k tournament picks
+    pub k: Option<i32>,
+    pub pairing: Pairing,
+    // how many rounds a swiss tournament plays
+    pub rounds: Option<i32>,
+    // to replay an earlier tournament's random choices, or a new seed if none
+    pub seed: Option<i64>,
+}
+
+impl Default for Format {
+    fn default() -> Format {
+        Format {
+            mode: Mode::Elimination,
+            k: None,
+            pairing: Pairing::LowestPick,
+            rounds: None,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Insertable,Debug)]
+#[table_name="tournaments"]
+pub struct NewTournament {
+    pub slot: i32,
+    pub title: Option<String>,
+    pub status: Status,
+    pub created_at: NaiveDateTime,
+    pub mode: Mode,
+    pub k: Option<i32>,
+    pub pairing: Pairing,
+    pub rounds: Option<i32>,
+    // where the tournament's random choices come from
+    pub seed: i64,
+}
+
+#[derive(Queryable,Insertable,Debug,Clone)]
 #[table_name="tournament_anime"]
 pub struct TournamentAnime {
     pub tournament: i32,
     pub anime: i32
 }
 
-#[derive(Queryable,Insertable,Debug)]
+// where one side of a bracket match comes from
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Source {
+    Anime(i32),
+    Winner(i32),
+    Loser(i32),
+}
+
+// A match in a bracket tournament, numbered in the order they're played. Each
+// side is either a seeded anime or the winner or loser of an earlier match.
+#[derive(Queryable,Insertable,Debug,Clone)]
+#[table_name="bracket_matches"]
+pub struct BracketMatch {
+    pub tournament: i32,
+    pub number: i32,
+    pub bracket: Bracket,
+    pub round: i32,
+    pub left_anime: Option<i32>,
+    pub left_winner_of: Option<i32>,
+    pub left_loser_of: Option<i32>,
+    pub right_anime: Option<i32>,
+    pub right_winner_of: Option<i32>,
+    pub right_loser_of: Option<i32>,
+}
+
+impl BracketMatch {
+    fn source(anime: Option<i32>, winner_of: Option<i32>, loser_of: Option<i32>) -> Source {
+        match (anime, winner_of, loser_of) {
+            (Some(anime), _, _) => Source::Anime(anime),
+            (_, Some(number), _) => Source::Winner(number),
+            (_, _, Some(number)) => Source::Loser(number),
+            // can't happen for a match that was built properly
+            _ => Source::Anime(0)
+        }
+    }
+
+    fn columns(source: Source) -> (Option<i32>, Option<i32>, Option<i32>) {
+        match source {
+            Source::Anime(anime) => (Some(anime), None, None),
+            Source::Winner(number) => (None, Some(number), None),
+            Source::Loser(number) => (None, None, Some(number)),
+        }
+    }
+
+    pub fn left(&self) -> Source {
+        BracketMatch::source(self.left_anime, self.left_winner_of, self.left_loser_of)
+    }
+
+    pub fn right(&self) -> Source {
+        BracketMatch::source(self.right_anime, self.right_winner_of, self.right_loser_of)
+    }
+
+    pub fn set_left(&mut self, source: Source) {
+        let (anime, winner_of, loser_of) = BracketMatch::columns(source);
+        self.left_anime = anime;
+        self.left_winner_of = winner_of;
+        self.left_loser_of = loser_of;
+    }
+
+    pub fn set_right(&mut self, source: Source) {
+        let (anime, winner_of, loser_of) = BracketMatch::columns(source);
+        self.right_anime = anime;
+        self.right_winner_of = winner_of;
+        self.right_loser_of = loser_of;
+    }
+}
+
+#[derive(Queryable,Debug,Clone,Serialize)]
+pub struct Member {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Queryable,Insertable,Debug,Clone)]
+#[table_name="tournament_members"]
+pub struct TournamentMember {
+    pub tournament: i32,
+    pub member: i32
+}
+
+// the voter of a room's ballot, which stands for all of the tournament's voters
+pub const ROOM_VOTER: i32 = 0;
+
+// one member's answer to a pair, or a room's
+#[derive(Queryable,Insertable,Debug,Clone)]
+#[table_name="decisions"]
+pub struct Ballot {
+    pub tournament: i32,
+    pub left_anime: i32,
+    pub right_anime: i32,
+    pub pick: Option<bool>,
+    pub voter: i32,
+    // the order the tournament's ballots were cast in
+    pub number: i32,
+    // which time the pair meets, as two anime can meet again in a bracket
+    pub meeting: i32,
+}
+
+// what the voters decided for a pair, all their ballots put together
+#[derive(Debug,Clone,Serialize)]
 pub struct Decision {
     pub tournament: i32,
     pub left_anime: i32,
     pub right_anime: i32,
-    pub pick: Option<bool>
+    // true for the left anime, false for the right, and None for a draw
+    pub pick: Option<bool>,
+    // 0 the first time the pair meets, 1 the next
+    pub meeting: i32,
+}
+
+// whether a member would watch an anime, in an approval tournament
+#[derive(Queryable,Insertable,Debug,Clone)]
+#[table_name="approval_ballots"]
+pub struct ApprovalBallot {
+    pub tournament: i32,
+    pub voter: i32,
+    pub anime: i32,
+    pub approved: bool,
+}
+
+// where a member put an anime on their ranked ballot, 1 for their first choice
+#[derive(Queryable,Insertable,Debug,Clone)]
+#[table_name="ranked_ballots"]
+pub struct RankedBallot {
+    pub tournament: i32,
+    pub voter: i32,
+    pub anime: i32,
+    pub position: i32,
+}
+
+// A tournament's crowd voting room, which members join from their phones with
+// its code. Each matchup is open to their votes for `seconds`, or until they've
+// all voted.
+#[derive(Queryable,Insertable,Debug,Clone)]
+#[table_name="rooms"]
+pub struct Room {
+    pub tournament: i32,
+    pub code: String,
+    pub seconds: i32,
+    // the number of the matchup open now, counting from 1
+    pub matchup: i32,
+    pub opened_at: NaiveDateTime,
+}
+
+impl Room {
+    pub fn closes_at(&self) -> NaiveDateTime {
+        self.opened_at + chrono::Duration::seconds(self.seconds.into())
+    }
+}
+
+#[derive(Queryable,Insertable,Debug,Clone)]
+#[table_name="room_members"]
+pub struct RoomMember {
+    pub tournament: i32,
+    pub member: i32,
+}
+
+// one member's vote in a room, kept once the majority has been saved as the pick
+#[derive(Queryable,Insertable,Debug,Clone)]
+#[table_name="room_ballots"]
+pub struct RoomBallot {
+    pub tournament: i32,
+    pub matchup: i32,
+    pub voter: i32,
+    pub left_anime: i32,
+    pub right_anime: i32,
+    pub pick: Option<bool>,
+    pub cast_at: NaiveDateTime,
+}
+
+impl RoomBallot {
+    // as a tournament ballot, to be counted the same way
+    pub fn ballot(&self) -> Ballot {
+        Ballot {
+            tournament: self.tournament,
+            left_anime: self.left_anime,
+            right_anime: self.right_anime,
+            pick: self.pick,
+            voter: self.voter,
+            number: self.matchup,
+            meeting: 0,
+        }
+    }
+}
+
+// how anime are rated across tournaments
+#[derive(Debug,Clone,Copy,PartialEq,AsExpression,FromSqlRow)]
+#[sql_type="Text"]
+pub enum System {
+    Elo,
+    Glicko2
+}
+
+impl System {
+    pub fn name(&self) -> &'static str {
+        match self {
+            System::Elo => "elo",
+            System::Glicko2 => "glicko2",
+        }
+    }
+}
+
+impl FromStr for System {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<System, String> {
+        match s {
+            "elo" => Ok(System::Elo),
+            "glicko2" => Ok(System::Glicko2),
+            _ => Err(format!("Unknown rating system: {}", s))
+        }
+    }
+}
+
+text_column!(System);
+
+// an anime's rating after one decision; Glicko-2 also has a deviation and volatility
+#[derive(Queryable,Debug,Clone)]
+pub struct Rating {
+    pub id: i32,
+    pub anime: i32,
+    pub system: System,
+    pub rating: f64,
+    pub deviation: Option<f64>,
+    pub volatility: Option<f64>,
+    pub tournament: i32,
+    pub created_at: NaiveDateTime,
+    // which time the pair met in the decision it's from
+    pub meeting: i32,
+}
+
+#[derive(Insertable,Debug)]
+#[table_name="ratings"]
+pub struct NewRating {
+    pub anime: i32,
+    pub system: System,
+    pub rating: f64,
+    pub deviation: Option<f64>,
+    pub volatility: Option<f64>,
+    pub tournament: i32,
+    pub created_at: NaiveDateTime,
+    pub meeting: i32,
 }
@@ -0,0 +1,177 @@
+// Rating anime from their decisions, so their strength carries over from one
+// tournament to the next.
+//
+// Every decision is rated on its own as it's saved: for Glicko-2 that makes each
+// decision a rating period with a single game.
+
+use std::f64::consts::PI;
+
+use super::models::*;
+
+const ELO_START: f64 = 1500.0;
+const ELO_K: f64 = 32.0;
+
+const GLICKO_START: f64 = 1500.0;
+const GLICKO_DEVIATION: f64 = 350.0;
+const GLICKO_VOLATILITY: f64 = 0.06;
+// how much the volatility can change, between 0.3 and 1.2
+const GLICKO_TAU: f64 = 0.5;
+// converts between the Glicko and Glicko-2 scales
+const GLICKO_SCALE: f64 = 173.7178;
+const EPSILON: f64 = 0.000_001;
+
+#[derive(Debug,Clone,Copy)]
+pub struct Strength {
+    pub rating: f64,
+    pub deviation: Option<f64>,
+    pub volatility: Option<f64>,
+}
+
+impl Strength {
+    pub fn of(rating: &Rating) -> Strength {
+        Strength {
+            rating: rating.rating,
+            deviation: rating.deviation,
+            volatility: rating.volatility,
+        }
+    }
+}
+
+// what an anime that has never been decided on starts with
+pub fn initial(system: System) -> Strength {
+    match system {
+        System::Elo => Strength { rating: ELO_START, deviation: None, volatility: None },
+        System::Glicko2 => Strength {
+            rating: GLICKO_START,
+            deviation: Some(GLICKO_DEVIATION),
+            volatility: Some(GLICKO_VOLATILITY),
+        },
+    }
+}
+
+// `score` is 1 for a win, 0 for a loss and 0.5 for a draw
+pub fn update(system: System, player: Strength, opponent: Strength, score: f64) -> Strength {
+    match system {
+        System::Elo => elo(player, opponent, score),
+        System::Glicko2 => glicko2(player, opponent, score),
+    }
+}
+
+fn elo(player: Strength, opponent: Strength, score: f64) -> Strength {
+    let expected = 1.0 / (1.0 + 10f64.powf((opponent.rating - player.rating) / 400.0));
+    Strength {
+        rating: player.rating + ELO_K * (score - expected),
+        deviation: None,
+        volatility: None,
+    }
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt()
+}
+
+// Glickman's Glicko-2, step by step
+fn glicko2(player: Strength, opponent: Strength, score: f64) -> Strength {
+    let start = initial(System::Glicko2);
+    let mu = (player.rating - GLICKO_START) / GLICKO_SCALE;
+    let phi = player.deviation.or(start.deviation).unwrap_or(GLICKO_DEVIATION) / GLICKO_SCALE;
+    let sigma = player.volatility.or(start.volatility).unwrap_or(GLICKO_VOLATILITY);
+    let mu_j = (opponent.rating - GLICKO_START) / GLICKO_SCALE;
+    let phi_j = opponent.deviation.or(start.deviation).unwrap_or(GLICKO_DEVIATION) / GLICKO_SCALE;
+
+    let g_j = g(phi_j);
+    let expected = 1.0 / (1.0 + (-g_j * (mu - mu_j)).exp());
+    let v = 1.0 / (g_j * g_j * expected * (1.0 - expected));
+    let delta = v * g_j * (score - expected);
+
+    // the new volatility, found with the Illinois algorithm
+    let a = (sigma * sigma).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2)) - (x - a) / (GLICKO_TAU * GLICKO_TAU)
+    };
+    let mut big_a = a;
+    let mut big_b = if delta * delta > phi * phi + v {
+        (delta * delta - phi * phi - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * GLICKO_TAU) < 0.0 {
+            k += 1.0;
+        }
+        a - k * GLICKO_TAU
+    };
+    let mut f_a = f(big_a);
+    let mut f_b = f(big_b);
+    while (big_b - big_a).abs() > EPSILON {
+        let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+        let f_c = f(big_c);
+        if f_c * f_b <= 0.0 {
+            big_a = big_b;
+            f_a = f_b;
+        } else {
+            f_a /= 2.0;
+        }
+        big_b = big_c;
+        f_b = f_c;
+    }
+    let new_sigma = (big_a / 2.0).exp();
+
+    let phi_star = (phi * phi + new_sigma * new_sigma).sqrt();
+    let new_phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+    let new_mu = mu + new_phi * new_phi * g_j * (score - expected);
+
+    Strength {
+        rating: GLICKO_SCALE * new_mu + GLICKO_START,
+        deviation: Some(GLICKO_SCALE * new_phi),
+        volatility: Some(new_sigma),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.001
+    }
+
+    #[test]
+    fn elo_moves_evenly_matched_anime_by_half_of_k() {
+        let start = initial(System::Elo);
+        assert!(close(update(System::Elo, start, start, 1.0).rating, 1516.0));
+        assert!(close(update(System::Elo, start, start, 0.0).rating, 1484.0));
+        assert!(close(update(System::Elo, start, start, 0.5).rating, 1500.0));
+    }
+
+    #[test]
+    fn elo_moves_less_for_the_expected_result() {
+        let strong = Strength { rating: 1700.0, deviation: None, volatility: None };
+        let weak = initial(System::Elo);
+        let gain = update(System::Elo, strong, weak, 1.0).rating - strong.rating;
+        let loss = weak.rating - update(System::Elo, weak, strong, 0.0).rating;
+        assert!(gain < 16.0);
+        assert!(close(gain, loss));
+    }
+
+    #[test]
+    fn glicko2_from_the_start() {
+        let start = initial(System::Glicko2);
+        let won = update(System::Glicko2, start, start, 1.0);
+        let lost = update(System::Glicko2, start, start, 0.0);
+
+        assert!(close(won.rating, 1662.3109));
+        assert!(close(lost.rating, 1337.6891));
+        // one game says something, so the deviation shrinks
+        assert!(won.deviation.unwrap() < GLICKO_DEVIATION);
+        assert!((won.volatility.unwrap() - GLICKO_VOLATILITY).abs() < 0.01);
+    }
+
+    #[test]
+    fn glicko2_moves_an_uncertain_rating_further() {
+        let start = initial(System::Glicko2);
+        let settled = Strength { deviation: Some(50.0), ..start };
+        let unsettled = update(System::Glicko2, start, start, 1.0).rating - start.rating;
+        let settled = update(System::Glicko2, settled, start, 1.0).rating - start.rating;
+        assert!(settled > 0.0 && settled < unsettled);
+    }
+}
@@ -0,0 +1,253 @@
+// Putting every voter's ballots together into one result.
+//
+// A majority per pair can go round in a cycle once several people vote, so these
+// work from the whole table of pairwise votes instead, the way election methods do.
+
+use std::str::FromStr;
+
+use super::models::*;
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Method {
+    Schulze,
+    Copeland,
+    RankedPairs
+}
+
+impl Method {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Method::Schulze => "schulze",
+            Method::Copeland => "copeland",
+            Method::RankedPairs => "ranked-pairs",
+        }
+    }
+}
+
+impl FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Method, String> {
+        match s {
+            "schulze" => Ok(Method::Schulze),
+            "copeland" => Ok(Method::Copeland),
+            "ranked-pairs" => Ok(Method::RankedPairs),
+            _ => Err(format!("Unknown results method: {}", s))
+        }
+    }
+}
+
+// how many ballots picked each anime over each other one
+pub struct Matrix {
+    pub anime: Vec<i32>,
+    // prefers[i][j] is the number of ballots for anime[i] over anime[j]
+    pub prefers: Vec<Vec<i32>>,
+}
+
+impl Matrix {
+    // draws don't count for either side
+    pub fn new(anime: &[i32], ballots: &[Ballot]) -> Matrix {
+        let mut matrix = Matrix {
+            anime: anime.to_vec(),
+            prefers: vec![vec![0; anime.len()]; anime.len()],
+        };
+
+        for ballot in ballots {
+            let (winner, loser) = match Pick::to_pick(ballot.pick) {
+                Pick::Left => (ballot.left_anime, ballot.right_anime),
+                Pick::Right => (ballot.right_anime, ballot.left_anime),
+                Pick::Undecided => continue
+            };
+            if let (Some(i), Some(j)) = (matrix.index(winner), matrix.index(loser)) {
+                matrix.prefers[i][j] += 1;
+            }
+        }
+        matrix
+    }
+
+    fn index(&self, anime_id: i32) -> Option<usize> {
+        self.anime.iter().position(|a| *a == anime_id)
+    }
+
+    pub fn votes(&self, a: i32, b: i32) -> i32 {
+        match (self.index(a), self.index(b)) {
+            (Some(i), Some(j)) => self.prefers[i][j],
+            _ => 0
+        }
+    }
+
+    fn beats(&self, i: usize, j: usize) -> bool {
+        self.prefers[i][j] > self.prefers[j][i]
+    }
+}
+
+// the anime that beats every other one head to head, if there is one
+pub fn condorcet_winner(matrix: &Matrix) -> Option<i32> {
+    let n = matrix.anime.len();
+    (0..n).find(|i| (0..n).all(|j| j == *i || matrix.beats(*i, j)))
+        .map(|i| matrix.anime[i])
+}
+
+// best first
+pub fn rank(method: Method, matrix: &Matrix) -> Vec<i32> {
+    match method {
+        Method::Schulze => schulze(matrix),
+        Method::Copeland => copeland(matrix),
+        Method::RankedPairs => ranked_pairs(matrix),
+    }
+}
+
+// Schulze: the strength of the strongest chain of wins from each anime to each
+// other one, and an anime goes above another if its chain to it is stronger.
+pub fn schulze(matrix: &Matrix) -> Vec<i32> {
+    let n = matrix.anime.len();
+    let mut strength: Vec<Vec<i32>> = (0..n)
+        .map(|i| (0..n).map(|j| if matrix.beats(i, j) { matrix.prefers[i][j] } else { 0 }).collect())
+        .collect();
+
+    for k in 0..n {
+        for i in 0..n {
+            for j in 0..n {
+                if i != j && i != k && j != k {
+                    strength[i][j] = strength[i][j].max(strength[i][k].min(strength[k][j]));
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|i| -((0..n).filter(|j| strength[*i][*j] > strength[*j][*i]).count() as i32));
+    order.into_iter().map(|i| matrix.anime[i]).collect()
+}
+
+// Copeland: two points for each anime beaten head to head and one for each tie,
+// counting only the pairs that were voted on
+pub fn copeland(matrix: &Matrix) -> Vec<i32> {
+    let n = matrix.anime.len();
+    let score = |i: usize| -> i32 {
+        (0..n).filter(|j| *j != i && matrix.prefers[i][*j] + matrix.prefers[*j][i] > 0)
+            .map(|j| match matrix.prefers[i][j] - matrix.prefers[j][i] {
+                d if d > 0 => 2,
+                0 => 1,
+                _ => 0
+            })
+            .sum()
+    };
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|i| -score(*i));
+    order.into_iter().map(|i| matrix.anime[i]).collect()
+}
+
+// Ranked pairs: lock in the wins from the biggest majority down, skipping any
+// that would make a cycle, then read the order off what's locked in.
+pub fn ranked_pairs(matrix: &Matrix) -> Vec<i32> {
+    let n = matrix.anime.len();
+    let mut majorities: Vec<(usize, usize)> = vec![];
+    for i in 0..n {
+        for j in 0..n {
+            if i != j && matrix.beats(i, j) {
+                majorities.push((i, j));
+            }
+        }
+    }
+    majorities.sort_by_key(|(i, j)| (-matrix.prefers[*i][*j], matrix.prefers[*j][*i]));
+
+    let mut locked = vec![vec![false; n]; n];
+    for (i, j) in majorities {
+        if !reaches(&locked, j, i) {
+            locked[i][j] = true;
+        }
+    }
+
+    // take off the anime nothing left is locked in over, one at a time
+    let mut left: Vec<usize> = (0..n).collect();
+    let mut order = vec![];
+    while !left.is_empty() {
+        let next = left.iter().position(|j| !left.iter().any(|i| locked[*i][*j])).unwrap_or(0);
+        order.push(matrix.anime[left.remove(next)]);
+    }
+    order
+}
+
+fn reaches(locked: &[Vec<bool>], from: usize, to: usize) -> bool {
+    let mut seen = vec![false; locked.len()];
+    let mut stack = vec![from];
+    while let Some(i) = stack.pop() {
+        if i == to {
+            return true;
+        }
+        if !seen[i] {
+            seen[i] = true;
+            stack.extend((0..locked.len()).filter(|j| locked[i][*j]));
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1 beats 2 by 6 to 3, 2 beats 3 by 7 to 2, and 3 beats 1 by 5 to 4: a cycle
+    // whose weakest link is 3 over 1
+    fn cycle() -> Matrix {
+        Matrix {
+            anime: vec![1, 2, 3],
+            prefers: vec![vec![0, 6, 4], vec![3, 0, 7], vec![5, 2, 0]],
+        }
+    }
+
+    fn ballot(left_anime: i32, right_anime: i32, pick: Pick) -> Ballot {
+        Ballot { tournament: 1, left_anime, right_anime, pick: Pick::to_opt(pick), voter: 1, number: 0, meeting: 0 }
+    }
+
+    #[test]
+    fn the_matrix_counts_each_ballot_for_its_pick() {
+        let ballots = vec![
+            ballot(1, 2, Pick::Left),
+            ballot(2, 1, Pick::Right),
+            ballot(2, 1, Pick::Left),
+            ballot(1, 2, Pick::Undecided),
+        ];
+        let matrix = Matrix::new(&[1, 2], &ballots);
+        assert_eq!(matrix.votes(1, 2), 2);
+        assert_eq!(matrix.votes(2, 1), 1);
+        assert_eq!(matrix.votes(1, 3), 0);
+    }
+
+    #[test]
+    fn a_cycle_has_no_condorcet_winner() {
+        assert_eq!(condorcet_winner(&cycle()), None);
+
+        let matrix = Matrix { anime: vec![1, 2, 3], prefers: vec![vec![0, 1, 1], vec![2, 0, 2], vec![2, 1, 0]] };
+        assert_eq!(condorcet_winner(&matrix), Some(2));
+    }
+
+    #[test]
+    fn schulze_breaks_the_cycle_at_its_weakest_link() {
+        assert_eq!(rank(Method::Schulze, &cycle()), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn ranked_pairs_skips_the_win_that_would_make_a_cycle() {
+        assert_eq!(rank(Method::RankedPairs, &cycle()), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn copeland_counts_wins_and_ties_head_to_head() {
+        // each anime in the cycle wins once, so they're tied and keep their order
+        assert_eq!(rank(Method::Copeland, &cycle()), vec![1, 2, 3]);
+
+        // 3 beats 1 and ties with 2, while 2 and 1 were never voted on
+        let matrix = Matrix { anime: vec![1, 2, 3], prefers: vec![vec![0, 0, 1], vec![0, 0, 2], vec![3, 2, 0]] };
+        assert_eq!(copeland(&matrix), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn methods_are_named_as_they_are_parsed() {
+        for method in &[Method::Schulze, Method::Copeland, Method::RankedPairs] {
+            assert_eq!(method.name().parse::<Method>(), Ok(*method));
+        }
+    }
+}
@@ -3,24 +3,134 @@ table! {
         id -> Integer,
         name -> Varchar,
         episodes -> Nullable<Integer>,
-        slot1 -> Bool,
-        slot2 -> Bool,
-        slot3 -> Bool,
     }
 }
 
 table! {
-    decisions (tournament, left_anime, right_anime) {
+    anime_slots (anime, slot) {
+        anime -> Integer,
+        slot -> Integer,
+    }
+}
+
+table! {
+    approval_ballots (tournament, voter, anime) {
+        tournament -> Integer,
+        voter -> Integer,
+        anime -> Integer,
+        approved -> Bool,
+    }
+}
+
+table! {
+    bracket_matches (tournament, number) {
+        tournament -> Integer,
+        number -> Integer,
+        bracket -> Varchar,
+        round -> Integer,
+        left_anime -> Nullable<Integer>,
+        left_winner_of -> Nullable<Integer>,
+        left_loser_of -> Nullable<Integer>,
+        right_anime -> Nullable<Integer>,
+        right_winner_of -> Nullable<Integer>,
+        right_loser_of -> Nullable<Integer>,
+    }
+}
+
+table! {
+    decisions (tournament, left_anime, right_anime, voter, meeting) {
+        tournament -> Integer,
+        left_anime -> Integer,
+        right_anime -> Integer,
+        pick -> Nullable<Bool>,
+        voter -> Integer,
+        number -> Integer,
+        meeting -> Integer,
+    }
+}
+
+table! {
+    members (id) {
+        id -> Integer,
+        name -> Varchar,
+    }
+}
+
+table! {
+    ranked_ballots (tournament, voter, anime) {
+        tournament -> Integer,
+        voter -> Integer,
+        anime -> Integer,
+        position -> Integer,
+    }
+}
+
+table! {
+    ratings (id) {
+        id -> Integer,
+        anime -> Integer,
+        system -> Varchar,
+        rating -> Double,
+        deviation -> Nullable<Double>,
+        volatility -> Nullable<Double>,
+        tournament -> Integer,
+        created_at -> Timestamp,
+        meeting -> Integer,
+    }
+}
+
+table! {
+    room_ballots (tournament, matchup, voter) {
         tournament -> Integer,
+        matchup -> Integer,
+        voter -> Integer,
         left_anime -> Integer,
         right_anime -> Integer,
         pick -> Nullable<Bool>,
+        cast_at -> Timestamp,
+    }
+}
+
+table! {
+    room_members (tournament, member) {
+        tournament -> Integer,
+        member -> Integer,
+    }
+}
+
+table! {
+    rooms (tournament) {
+        tournament -> Integer,
+        code -> Varchar,
+        seconds -> Integer,
+        matchup -> Integer,
+        opened_at -> Timestamp,
+    }
+}
+
+table! {
+    slots (id) {
+        id -> Integer,
+        name -> Varchar,
+        position -> Integer,
+        duration -> Nullable<Integer>,
+        max_episodes -> Nullable<Integer>,
     }
 }
 
 table! {
     tournaments (id) {
         id -> Integer,
+        slot -> Integer,
+        title -> Nullable<Varchar>,
+        status -> Varchar,
+        created_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        mode -> Varchar,
+        k -> Nullable<Integer>,
+        pairing -> Varchar,
+        rounds -> Nullable<Integer>,
+        seed -> BigInt,
     }
 }
 
@@ -31,9 +141,27 @@ table! {
     }
 }
 
+table! {
+    tournament_members (tournament, member) {
+        tournament -> Integer,
+        member -> Integer,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(
     anime,
+    anime_slots,
+    approval_ballots,
+    bracket_matches,
     decisions,
+    members,
+    ranked_ballots,
+    ratings,
+    room_ballots,
+    room_members,
+    rooms,
+    slots,
     tournaments,
     tournament_anime,
+    tournament_members,
 );